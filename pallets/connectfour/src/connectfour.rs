@@ -16,6 +16,19 @@ impl Logic {
         true
     }
 
+    pub fn stones(board: [[u8; 6]; 7], player: u8) -> u8 {
+        let mut stones = 0;
+        for x_pos in 0..board.len() {
+            for y_pos in 0..board[0].len() {
+                if board[x_pos][y_pos] == player {
+                    stones += 1;
+                }
+            }
+        }
+
+        stones
+    }
+
     pub fn evaluate(board: [[u8; 6]; 7], player: u8) -> bool {
         // horizontalCheck 
         for y in 0..board[0].len() {
@@ -67,6 +80,23 @@ impl Logic {
         return false;
     }
     
+    pub fn moves(board: [[u8; 6]; 7]) -> u8 {
+        let mut moves = 0;
+        for x_pos in 0..board.len() {
+            for y_pos in 0..board[0].len() {
+                if board[x_pos][y_pos] > 0 {
+                    moves += 1;
+                }
+            }
+        }
+
+        moves
+    }
+
+    pub fn can_add_stone(board: [[u8; 6]; 7], column: u8) -> bool {
+        (column as usize) < board.len() && board[column as usize][0] == 0
+    }
+
     pub fn add_stone(board: &mut [[u8; 6]; 7], column: u8, player: u8) -> bool {
         if board[column as usize][0] > 0 {
             return false;
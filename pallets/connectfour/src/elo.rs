@@ -0,0 +1,50 @@
+/// Expected score in per mille of the lower rated player, for rating differences in steps of 25.
+const EXPECTED: [u32; 33] = [
+    500, 464, 429, 394, 360, 327, 297, 267, 240, 215, 192, 170, 151, 133, 118, 104, 91,
+    80, 70, 61, 53, 46, 40, 35, 31, 27, 23, 20, 17, 15, 13, 11, 10,
+];
+const STEP: u32 = 25;
+
+pub const WIN: u32 = 1000;
+pub const DRAW: u32 = 500;
+pub const LOSS: u32 = 0;
+
+pub struct Elo {
+}
+
+impl Elo {
+
+    /// Expected score in per mille of a player against an opponent.
+    pub fn expected(rating: u32, opponent: u32) -> u32 {
+        let diff = if rating > opponent { rating - opponent } else { opponent - rating };
+        let index = (diff / STEP) as usize;
+
+        let expected = if index + 1 >= EXPECTED.len() {
+            EXPECTED[EXPECTED.len() - 1]
+        } else {
+            // interpolate between the two closest differences
+            let rest = diff % STEP;
+            EXPECTED[index] - (EXPECTED[index] - EXPECTED[index + 1]) * rest / STEP
+        };
+
+        if rating > opponent { 1000 - expected } else { expected }
+    }
+
+    /// Rating change of a player scoring a result in per mille against an opponent.
+    pub fn change(rating: u32, opponent: u32, score: u32, k_factor: u32) -> i32 {
+        let expected = Elo::expected(rating, opponent) as i32;
+        let delta = k_factor as i32 * (score as i32 - expected);
+
+        // round half away from zero
+        if delta >= 0 { (delta + 500) / 1000 } else { (delta - 500) / 1000 }
+    }
+
+    /// New rating of a player after a rating change, ratings don't drop below zero.
+    pub fn apply(rating: u32, change: i32) -> u32 {
+        if change >= 0 {
+            rating.saturating_add(change as u32)
+        } else {
+            rating.saturating_sub((-change) as u32)
+        }
+    }
+}
@@ -6,19 +6,36 @@
 
 use codec::{Encode, Decode};
 use frame_support::{
-	log,
-	traits::{Randomness, LockIdentifier, schedule::{Named, DispatchTime}},
+	log, ensure, PalletId,
+	dispatch::{DispatchResult, DispatchError},
+	storage::bounded_vec::BoundedVec,
+	traits::{
+		Get, LockIdentifier, Currency, ReservableCurrency, Imbalance, IsSubType,
+		BalanceStatus, fungibles,
+	},
+	weights::Weight,
 };
 use frame_system::{
 	WeightInfo
 };
 use sp_runtime::{
-	traits::{Hash, Dispatchable, TrailingZeroInput}
+	Perbill, RuntimeDebug,
+	helpers_128bit::multiply_by_rational,
+	traits::{
+		Hash, One, Zero, Saturating, SaturatedConversion, AccountIdConversion, SignedExtension,
+		DispatchInfoOf, PostDispatchInfoOf,
+	},
+	transaction_validity::{
+		TransactionValidity, TransactionValidityError, ValidTransaction, InvalidTransaction,
+	},
 };
-use sp_std::vec::{
-	Vec
+use sp_std::{
+	convert::TryFrom,
+	marker::PhantomData,
+	vec::Vec,
 };
 use log::info;
+use pallet_transaction_payment::OnChargeTransaction;
 
 // Re-export pallet items so that they can be accessed from the crate namespace.
 pub use pallet::*;
@@ -39,10 +56,14 @@ pub mod weights;
 pub mod connectfour;
 use connectfour::{Logic};
 
+/// Elo rating calculation with integer math.
+pub mod elo;
+use elo::Elo;
+
 const CONNECTFOUR_ID: LockIdentifier = *b"connect4";
 
 /// A type alias for the balance type from this pallet's point of view.
-//type BalanceOf<T> = <T as pallet_balances::Config>::Balance;
+type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 //const MILLICENTS: u32 = 1_000_000_000;
 
 #[derive(Encode, Decode, Clone, PartialEq)]
@@ -54,6 +75,19 @@ pub enum BoardState<AccountId> {
 
 impl<AccountId> Default for BoardState<AccountId> { fn default() -> Self { Self::None } }
 
+/// How a board came to an end.
+#[derive(Clone, Copy, PartialEq)]
+pub enum EndReason {
+	/// Four connected stones or a full board.
+	Played,
+	/// A player let the turn deadline pass.
+	Timeout,
+	/// A player resigned.
+	Resigned,
+	/// Root finished the board.
+	Forced,
+}
+
 /// Connect four board structure containing two players and the board
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -67,14 +101,192 @@ pub struct BoardStruct<Hash, AccountId, BlockNumber, BoardState> {
 	board_state: BoardState,
 }
 
+/// Stake both players of a wagered board put up when they commit to play it, reserved in the
+/// native currency or escrowed in an asset.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Wager<AssetId, Balance> {
+	asset: Option<AssetId>,
+	stake: Balance,
+}
+
+/// Session key authorised by a player to place moves on one board on their behalf.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct MoveKey<AccountId, Hash, BlockNumber> {
+	owner: AccountId,
+	board_id: Hash,
+	expires: Option<BlockNumber>,
+}
+
+/// Commit-reveal coin toss deciding the starting player of a board. Each player commits the
+/// hash of their account and a secret, and reveals the secret once both commits are in.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct CoinToss<Hash> {
+	red_commit: Option<Hash>,
+	blue_commit: Option<Hash>,
+	red_secret: Option<Hash>,
+	blue_secret: Option<Hash>,
+}
+
+impl<Hash> CoinToss<Hash> {
+	/// Both players committed, the toss waits for the reveals.
+	fn committed(&self) -> bool {
+		self.red_commit.is_some() && self.blue_commit.is_some()
+	}
+
+	/// Which players did their part of the current phase, as (red, blue).
+	fn done(&self) -> (bool, bool) {
+		if self.committed() {
+			(self.red_secret.is_some(), self.blue_secret.is_some())
+		} else {
+			(self.red_commit.is_some(), self.blue_commit.is_some())
+		}
+	}
+}
+
+/// Prediction market of spectators backing the players of a running board.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Market<BlockNumber, Balance> {
+	closes: BlockNumber,
+	red_pool: Balance,
+	blue_pool: Balance,
+	bettors: u32,
+}
+
+/// Funds a spectator reserved to back each player of a board.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Bet<Balance> {
+	red: Balance,
+	blue: Balance,
+}
+
+/// Fee budget a sponsor reserved to pay the moves of sponsored players and boards.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Sponsorship<Balance, BlockNumber> {
+	budget: Balance,
+	expires: BlockNumber,
+}
+
+/// Elo rating of a player, the number of rated games played and the season of the last one.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Rating {
+	rating: u32,
+	games: u32,
+	season: u32,
+}
+
+impl Default for Rating { fn default() -> Self { Self { rating: INITIAL_RATING, games: 0, season: 0 } } }
+
+/// Results and habits of a player over all finished boards.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Stats {
+	wins: u32,
+	losses: u32,
+	draws: u32,
+	timeouts_caused: u32,
+	timeouts_suffered: u32,
+	resignations: u32,
+	streak: u32,
+	best_streak: u32,
+	first_player_games: u32,
+	games: u32,
+	moves: u32,
+}
+
+/// Game mode of a board, only ranked boards update the ratings of their players.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum GameMode {
+	Ranked,
+	Casual,
+}
+
+impl Default for GameMode { fn default() -> Self { Self::Ranked } }
+
+/// How a board is played, the game mode and the number of blocks a turn lasts.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct GameSettings {
+	mode: GameMode,
+	turn_blocks: u8,
+}
+
+impl Default for GameSettings { fn default() -> Self { Self { mode: GameMode::Ranked, turn_blocks: MAX_BLOCKS_PER_TURN } } }
+
+/// A player waiting in a matchmaking queue with the rating they queued with.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct QueueEntry<AccountId, BlockNumber> {
+	player: AccountId,
+	rating: u32,
+	since: BlockNumber,
+}
+
+/// Open challenge a player posted to the lobby, the first eligible account accepting it plays
+/// the creator.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Challenge<AccountId, AssetId, Balance, BlockNumber> {
+	creator: AccountId,
+	settings: GameSettings,
+	asset: Option<AssetId>,
+	stake: Balance,
+	min_rating: u32,
+	max_rating: u32,
+	friends_only: bool,
+	expires: BlockNumber,
+}
+
+/// Recent timeouts a player caused and the bans they served for stalling.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Penalty<BlockNumber> {
+	timeouts: u32,
+	last_timeout: BlockNumber,
+	bans: u32,
+	banned_until: BlockNumber,
+}
+
+/// A type alias for the wager of a board as stored by this pallet.
+type WagerOf<T> = Wager<<T as Config>::AssetId, BalanceOf<T>>;
+
+/// A type alias for a matchmaking queue entry as stored by this pallet.
+type QueueEntryOf<T> = QueueEntry<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+/// A type alias for an open challenge as stored by this pallet.
+type ChallengeOf<T> = Challenge<
+	<T as frame_system::Config>::AccountId,
+	<T as Config>::AssetId,
+	BalanceOf<T>,
+	<T as frame_system::Config>::BlockNumber,
+>;
+
+/// A type alias for the board structure as stored by this pallet.
+type BoardOf<T> = BoardStruct<
+	<T as frame_system::Config>::Hash,
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::BlockNumber,
+	BoardState<<T as frame_system::Config>::AccountId>,
+>;
+
 const PLAYER_1: u8 = 1;
 const PLAYER_2: u8 = 2;
+const MIN_BLOCKS_PER_TURN: u8 = 3;
 const MAX_BLOCKS_PER_TURN: u8 = 10;
 const CLEANUP_BOARDS_AFTER: u8 = 20;
+const INITIAL_RATING: u32 = 1500;
+const K_FACTOR: u32 = 20;
+const PROVISIONAL_K_FACTOR: u32 = 40;
+const PROVISIONAL_GAMES: u32 = 30;
 
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::{dispatch::{DispatchResult}, pallet_prelude::*};
+	use frame_support::{dispatch::{DispatchResult, DispatchResultWithPostInfo, PostDispatchInfo}, pallet_prelude::*};
 	use frame_system::pallet_prelude::*;
 
 	// important to use outside structs and consts
@@ -83,18 +295,142 @@ pub mod pallet {
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
 	pub trait Config: frame_system::Config {
-		
-		type Proposal: Parameter + Dispatchable<Origin=Self::Origin> + From<Call<Self>>;
 
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
 		
-		/// The generator used to supply randomness to contracts through `seal_random`.
-		type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
 
-		type Scheduler: Named<Self::BlockNumber, Self::Proposal, Self::PalletsOrigin>;
+		/// The currency used to reserve the board deposits and the wagered stakes.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Identifier of the assets boards can be wagered in.
+		type AssetId: Parameter + Copy;
+
+		/// The assets boards can be wagered in besides the native currency.
+		type Assets: fungibles::Transfer<Self::AccountId, AssetId = Self::AssetId, Balance = BalanceOf<Self>>;
+
+		/// The pallet id, used to derive the escrow account holding the asset stakes.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The deposit reserved from each player while a board is open, from the creator when the
+		/// board is created and from the opponent once they commit to it.
+		#[pallet::constant]
+		type BoardDeposit: Get<BalanceOf<Self>>;
+
+		/// Budget for the expired board deadlines handled at the start of a block, every board
+		/// counts once together with each bettor of its market.
+		#[pallet::constant]
+		type MaxDeadlinesPerBlock: Get<u32>;
+
+		/// The part of a wagered pot paid to the founder key as platform rake.
+		#[pallet::constant]
+		type Rake: Get<Perbill>;
+
+		/// The part of the board deposit slashed from a player letting a casual board time out.
+		#[pallet::constant]
+		type CasualTimeoutSlash: Get<Perbill>;
+
+		/// The part of the board deposit slashed from a player letting a wagered board time out.
+		#[pallet::constant]
+		type WageredTimeoutSlash: Get<Perbill>;
+
+		/// The part of a timeout slash paid to the opponent, the rest goes to the founder key.
+		#[pallet::constant]
+		type TimeoutSlashReward: Get<Perbill>;
+
+		/// The smallest amount a spectator can bet on a board.
+		#[pallet::constant]
+		type MinBet: Get<BalanceOf<Self>>;
+
+		/// Maximum number of spectators betting on a board.
+		#[pallet::constant]
+		type MaxBettorsPerBoard: Get<u32>;
+
+		/// Number of blocks after the board creation the market accepts bets.
+		#[pallet::constant]
+		type MarketPeriod: Get<Self::BlockNumber>;
+
+		/// Number of stones on the board after which the market doesn't accept bets anymore.
+		#[pallet::constant]
+		type MarketCloseMoves: Get<u8>;
+
+		/// The reward minted to the winner of a board, zero disables the rewards.
+		#[pallet::constant]
+		type WinReward: Get<BalanceOf<Self>>;
+
+		/// The reward minted to each player of a board ending in a draw.
+		#[pallet::constant]
+		type DrawReward: Get<BalanceOf<Self>>;
 
-		type PalletsOrigin: From<frame_system::RawOrigin<Self::AccountId>>;
+		/// Number of stones a finished board needs to be rewarded.
+		#[pallet::constant]
+		type MinRewardMoves: Get<u8>;
+
+		/// Number of last rewarded opponents a player isn't rewarded against again.
+		#[pallet::constant]
+		type RewardOpponentWindow: Get<u32>;
+
+		/// Number of blocks of a reward era.
+		#[pallet::constant]
+		type RewardEraLength: Get<Self::BlockNumber>;
+
+		/// Maximum amount of rewards minted during a reward era.
+		#[pallet::constant]
+		type MaxEraEmission: Get<BalanceOf<Self>>;
+
+		/// Number of players on the leaderboard.
+		#[pallet::constant]
+		type LeaderboardSize: Get<u32>;
+
+		/// Number of blocks of a season.
+		#[pallet::constant]
+		type SeasonLength: Get<Self::BlockNumber>;
+
+		/// The part of the distance to the initial rating a rating keeps into the next season.
+		#[pallet::constant]
+		type SeasonResetFactor: Get<Perbill>;
+
+		/// Rating difference waiting players get paired within right away.
+		#[pallet::constant]
+		type MatchWindow: Get<u32>;
+
+		/// Rating difference the pairing window widens by for each block a player waits.
+		#[pallet::constant]
+		type MatchWindowStep: Get<u32>;
+
+		/// Maximum number of players waiting in each matchmaking queue.
+		#[pallet::constant]
+		type MaxQueueSize: Get<u32>;
+
+		/// Maximum number of boards the matchmaking starts per block.
+		#[pallet::constant]
+		type MaxMatchesPerBlock: Get<u32>;
+
+		/// Maximum number of queued players the matchmaking compares per block, the scan resumes
+		/// in the next block where it stopped.
+		#[pallet::constant]
+		type MaxQueueComparisons: Get<u32>;
+
+		/// Maximum number of open challenges in the lobby.
+		#[pallet::constant]
+		type MaxOpenChallenges: Get<u32>;
+
+		/// Number of blocks a caused timeout counts as recent.
+		#[pallet::constant]
+		type TimeoutWindow: Get<Self::BlockNumber>;
+
+		/// Number of recent timeouts after which a player gets banned from starting boards.
+		#[pallet::constant]
+		type TimeoutThreshold: Get<u32>;
+
+		/// Number of blocks of the first ban, every further ban lasts twice as long.
+		#[pallet::constant]
+		type BanLength: Get<Self::BlockNumber>;
+
+		/// Maximum number of accounts on the friends list of a player.
+		#[pallet::constant]
+		type MaxFriends: Get<u32>;
 		// /// Weight information for extrinsics in this pallet.
 		//type WeightInfo: WeightInfo;
 	}
@@ -118,7 +454,7 @@ pub mod pallet {
 	#[pallet::storage]
 	#[pallet::getter(fn boards)]
 	/// Store all boards that are currently being played.
-	pub type Boards<T: Config> = StorageMap<_, Identity, T::Hash, BoardStruct<T::Hash, T::AccountId, T::BlockNumber, BoardState<T::AccountId>>, ValueQuery>;
+	pub type Boards<T: Config> = StorageMap<_, Identity, T::Hash, BoardOf<T>, ValueQuery>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn player_board)]
@@ -127,15 +463,174 @@ pub mod pallet {
 
 	#[pallet::storage]
 	#[pallet::getter(fn board_schedules)]
-	/// Store players active board, currently only one board per player allowed.
-	pub type BoardSchedules<T: Config> = StorageMap<_, Identity, T::Hash, Option<Vec<u8>>, ValueQuery>;
+	/// Store the block at which the current deadline of a board expires.
+	pub type BoardSchedules<T: Config> = StorageMap<_, Identity, T::Hash, T::BlockNumber>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn deadlines)]
+	/// Store the boards queued up to be handled at a given block, stale entries are skipped.
+	pub type Deadlines<T: Config> = StorageMap<_, Twox64Concat, T::BlockNumber, Vec<T::Hash>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn coin_tosses)]
+	/// Store the coin toss of boards whose starting player is not decided yet.
+	pub type CoinTosses<T: Config> = StorageMap<_, Identity, T::Hash, CoinToss<T::Hash>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn wagers)]
+	/// Store the stake of wagered boards until the game is settled.
+	pub type Wagers<T: Config> = StorageMap<_, Identity, T::Hash, WagerOf<T>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn min_stakes)]
+	/// Store the minimum stake of the assets boards can be wagered in.
+	pub type MinStakes<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, BalanceOf<T>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn move_keys)]
+	/// Store the session keys allowed to move for a player on their board.
+	pub type MoveKeys<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, MoveKey<T::AccountId, T::Hash, T::BlockNumber>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn player_move_key)]
+	/// Store the session key currently authorised by a player.
+	pub type PlayerMoveKey<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn board_deposits)]
+	/// Store the deposit still reserved from each player of a board, released on cleanup.
+	pub type BoardDeposits<T: Config> = StorageDoubleMap<_, Identity, T::Hash, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn stalled)]
+	/// Store the players who let a board time out, checked when the deposits get released.
+	pub type StalledPlayers<T: Config> = StorageDoubleMap<_, Identity, T::Hash, Blake2_128Concat, T::AccountId, bool, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn board_stakes)]
+	/// Store the stake each player of a wagered board put up, released when the wager is settled.
+	pub type BoardStakes<T: Config> = StorageDoubleMap<_, Identity, T::Hash, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn board_nonce)]
+	/// Store the number of boards created by an account, used to derive the next board id.
+	pub type BoardNonce<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn game_count)]
+	/// Store the number of the last game created.
+	pub type GameCount<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn board_number)]
+	/// Store the sequential game number of a board.
+	pub type BoardNumbers<T: Config> = StorageMap<_, Identity, T::Hash, u64>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn game_board)]
+	/// Store the board of a sequential game number.
+	pub type GameNumbers<T: Config> = StorageMap<_, Twox64Concat, u64, T::Hash>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn markets)]
+	/// Store the prediction market of running boards.
+	pub type Markets<T: Config> = StorageMap<_, Identity, T::Hash, Market<T::BlockNumber, BalanceOf<T>>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn bets)]
+	/// Store the bets of spectators on a board until the market is settled.
+	pub type Bets<T: Config> = StorageDoubleMap<_, Identity, T::Hash, Blake2_128Concat, T::AccountId, Bet<BalanceOf<T>>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn recent_opponents)]
+	/// Store the last opponents a player got rewarded against, oldest first.
+	pub type RecentOpponents<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Vec<T::AccountId>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn era_emission)]
+	/// Store the current reward era and the rewards minted during it.
+	pub type EraEmission<T: Config> = StorageValue<_, (T::BlockNumber, BalanceOf<T>), ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn sponsorships)]
+	/// Store the fee budget of sponsors.
+	pub type Sponsorships<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Sponsorship<BalanceOf<T>, T::BlockNumber>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn sponsored_players)]
+	/// Store the sponsor paying the fees of a player.
+	pub type SponsoredPlayers<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn sponsored_boards)]
+	/// Store the sponsor paying the fees of the moves on a board.
+	pub type SponsoredBoards<T: Config> = StorageMap<_, Identity, T::Hash, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn ratings)]
+	/// Store the Elo rating of players.
+	pub type Ratings<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Rating, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn player_stats)]
+	/// Store the statistics of players over all finished boards.
+	pub type PlayerStats<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Stats, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn leaderboard)]
+	/// Store the highest rated players of the current season, highest rating first.
+	pub type Leaderboard<T: Config> = StorageValue<_, BoundedVec<(T::AccountId, u32), T::LeaderboardSize>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn current_season)]
+	/// Store the number of the current season.
+	pub type CurrentSeason<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn season_standings)]
+	/// Store the final leaderboard of past seasons.
+	pub type SeasonStandings<T: Config> = StorageMap<_, Twox64Concat, u32, BoundedVec<(T::AccountId, u32), T::LeaderboardSize>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn match_queues)]
+	/// Store the players waiting for an opponent per game settings, longest waiting first.
+	pub type MatchQueues<T: Config> = StorageMap<_, Blake2_128Concat, GameSettings, BoundedVec<QueueEntryOf<T>, T::MaxQueueSize>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn queued_players)]
+	/// Store the game settings a waiting player queued for.
+	pub type QueuedPlayers<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, GameSettings>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn queue_cursor)]
+	/// Store where the matchmaking scan resumes, the queue, the waiting player and the opponent
+	/// compared next.
+	pub type QueueCursor<T: Config> = StorageValue<_, (u32, u32, u32), ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn board_settings)]
+	/// Store the game settings of boards, boards without an entry use the defaults.
+	pub type BoardSettings<T: Config> = StorageMap<_, Identity, T::Hash, GameSettings, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn lobby)]
+	/// Store the open challenges of the lobby, oldest first, one per creator.
+	pub type Lobby<T: Config> = StorageValue<_, BoundedVec<ChallengeOf<T>, T::MaxOpenChallenges>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn penalty_box)]
+	/// Store the recent timeouts and bans of players who let boards time out.
+	pub type PenaltyBox<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Penalty<T::BlockNumber>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn blocked)]
+	/// Store the accounts a player blocked from challenging them.
+	pub type BlockedAccounts<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, bool, ValueQuery>;
 
-	// Default value for Nonce
-	#[pallet::type_value]
-	pub fn NonceDefault<T: Config>() -> u64 { 0 }
-	// Nonce used for generating a different seed each time.
 	#[pallet::storage]
-	pub type Nonce<T: Config> = StorageValue<_, u64, ValueQuery, NonceDefault<T>>;
+	#[pallet::getter(fn friends)]
+	/// Store the friends of a player allowed to accept their friend-only challenges.
+	pub type Friends<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<T::AccountId, T::MaxFriends>, ValueQuery>;
 
 	// The genesis config type.
 	#[pallet::genesis_config]
@@ -164,15 +659,124 @@ pub mod pallet {
 	// Pallets use events to inform users when important changes are made.
 	// https://substrate.dev/docs/en/knowledgebase/runtime/events
 	#[pallet::event]
-	#[pallet::metadata(T::AccountId = "AccountId")]
+	#[pallet::metadata(T::AccountId = "AccountId", T::AssetId = "AssetId", BalanceOf<T> = "Balance")]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
 		/// Event documentation should end with an array that provides descriptive names for event
 		/// parameters. [something, who]
 		SomethingStored(u32, T::AccountId),
 		
-		/// A new board got created.
-		NewBoard(T::Hash),
+		/// A new board got created. [board, game number]
+		NewBoard(T::Hash, u64),
+
+		/// A board got finished, no winner means a draw.
+		/// [board, winner, red rating change, blue rating change]
+		GameFinished(T::Hash, Option<T::AccountId>, i32, i32),
+
+		/// A season ended and its leaderboard got archived. [season]
+		SeasonEnded(u32),
+
+		/// A player joined the matchmaking queue. [who]
+		Queued(T::AccountId),
+
+		/// A player left the matchmaking queue. [who]
+		Dequeued(T::AccountId),
+
+		/// A player posted an open challenge to the lobby. [creator]
+		ChallengePosted(T::AccountId),
+
+		/// An open challenge got cancelled by its creator. [creator]
+		ChallengeCancelled(T::AccountId),
+
+		/// An open challenge expired without being accepted. [creator]
+		ChallengeExpired(T::AccountId),
+
+		/// An open challenge got accepted and its board created. [creator, opponent, board]
+		ChallengeAccepted(T::AccountId, T::AccountId, T::Hash),
+
+		/// A player got banned from starting boards for repeated timeouts. [who, until]
+		PlayerBanned(T::AccountId, T::BlockNumber),
+
+		/// A player blocked an account from challenging them. [who, blocked]
+		AccountBlocked(T::AccountId, T::AccountId),
+
+		/// A player unblocked an account. [who, unblocked]
+		AccountUnblocked(T::AccountId, T::AccountId),
+
+		/// A player added an account to their friends list. [who, friend]
+		FriendAdded(T::AccountId, T::AccountId),
+
+		/// A player removed an account from their friends list. [who, friend]
+		FriendRemoved(T::AccountId, T::AccountId),
+
+		/// A board deposit was slashed from a player who never played. [board, who, amount]
+		DepositSlashed(T::Hash, T::AccountId, BalanceOf<T>),
+
+		/// A board got voided by root without a result. [board]
+		GameVoided(T::Hash),
+
+		/// A board got finished by root, no winner means a draw. [board, winner]
+		GameForceFinished(T::Hash, Option<T::AccountId>),
+
+		/// Orphaned player and board entries got purged by root. [players, boards]
+		OrphansPurged(u32, u32),
+
+		/// A player committed to the coin toss of a board. [board, who]
+		TossCommitted(T::Hash, T::AccountId),
+
+		/// The coin toss got revealed and decided the starting player. [board, starting player]
+		TossDecided(T::Hash, T::AccountId),
+
+		/// A player authorised a session key to move on their board. [board, who, key]
+		MoveKeyAuthorized(T::Hash, T::AccountId, T::AccountId),
+
+		/// A player revoked their session key. [who, key]
+		MoveKeyRevoked(T::AccountId, T::AccountId),
+
+		/// A player reserved or escrowed the stake of a wagered board. [board, who, stake]
+		StakePlaced(T::Hash, T::AccountId, BalanceOf<T>),
+
+		/// The winner of a wagered board received the stake of the opponent. [board, winner, amount]
+		WagerPaid(T::Hash, T::AccountId, BalanceOf<T>),
+
+		/// The rake of a wagered pot got paid to the founder key. [board, founder, amount]
+		RakePaid(T::Hash, T::AccountId, BalanceOf<T>),
+
+		/// The founder key got rotated by root. [founder]
+		FounderKeyChanged(T::AccountId),
+
+		/// Part of the deposit of a player who let the board time out got slashed. [board, who, amount]
+		TimeoutSlashed(T::Hash, T::AccountId, BalanceOf<T>),
+
+		/// A spectator backed a player of a board. [board, who, backed player, amount]
+		BetPlaced(T::Hash, T::AccountId, T::AccountId, BalanceOf<T>),
+
+		/// A spectator received their bet and share of the losing pool. [board, who, amount]
+		BetPaid(T::Hash, T::AccountId, BalanceOf<T>),
+
+		/// The bets on a board got refunded, as there was no winner or nobody backed them. [board]
+		BetsRefunded(T::Hash),
+
+		/// The minimum stake of an asset got set by root, none disallows the asset. [asset, min stake]
+		MinStakeSet(T::AssetId, Option<BalanceOf<T>>),
+
+		/// A player got rewarded for a finished board. [board, who, amount]
+		RewardPaid(T::Hash, T::AccountId, BalanceOf<T>),
+
+		/// A sponsor funded their fee budget. [sponsor, budget, expires]
+		SponsorshipFunded(T::AccountId, BalanceOf<T>, T::BlockNumber),
+
+		/// A sponsor closed their fee budget and got the rest back. [sponsor, refund]
+		SponsorshipClosed(T::AccountId, BalanceOf<T>),
+
+		/// A sponsor pays the fees of a player. [sponsor, player]
+		PlayerSponsored(T::AccountId, T::AccountId),
+
+		/// A sponsor stopped paying the fees of a player. [sponsor, player]
+		PlayerUnsponsored(T::AccountId, T::AccountId),
+
+		/// A sponsor pays the fees of the moves on a board. [sponsor, board]
+		BoardSponsored(T::AccountId, T::Hash),
 	}
 
 	// Errors inform users that something went wrong.
@@ -182,8 +786,6 @@ pub mod pallet {
 		NoneValue,
 		/// Errors should have helpful documentation associated with them.
 		StorageOverflow,
-		/// Couldn't put off a scheduler task as planned.
-		ScheduleError,
 		/// Player already has a board which is being played.
 		PlayerBoardExists,
 		/// Player board doesn't exist for this player.
@@ -194,6 +796,94 @@ pub mod pallet {
 		NotPlayerTurn,
 		/// There was an error while trying to execute something in the logic mod.
 		WrongLogic,
+		/// Player can't afford the board deposit.
+		InsufficientDeposit,
+		/// Only the player waiting for the opponent's turn can claim a timeout.
+		NotWaitingPlayer,
+		/// The turn deadline has not passed yet.
+		TurnNotExpired,
+		/// The account is not playing on this board.
+		NotBoardPlayer,
+		/// The starting player is not decided by the coin toss yet.
+		TossPending,
+		/// There is no coin toss running for this board.
+		NoCoinToss,
+		/// There is already a board with the same id.
+		BoardExists,
+		/// The column is full or outside of the board.
+		InvalidColumn,
+		/// The session key is a player or already authorised by another player.
+		MoveKeyInUse,
+		/// Player has no session key authorised.
+		NoMoveKey,
+		/// Player can't afford the stake of the wagered board.
+		InsufficientStake,
+		/// Player already committed to the coin toss.
+		AlreadyCommitted,
+		/// Both players need to commit before revealing.
+		TossNotCommitted,
+		/// Player already revealed the coin toss secret.
+		AlreadyRevealed,
+		/// The revealed secret doesn't match the commitment.
+		InvalidReveal,
+		/// Players can't bet on their own board.
+		PlayerCannotBet,
+		/// The bet is below the minimum bet.
+		BetTooSmall,
+		/// The market of the board doesn't accept bets anymore.
+		MarketClosed,
+		/// The market of the board reached the maximum number of bettors.
+		TooManyBettors,
+		/// Spectator can't afford the bet.
+		InsufficientBalance,
+		/// Boards can't be wagered in this asset.
+		AssetNotAllowed,
+		/// The stake is below the minimum stake of the asset.
+		StakeTooSmall,
+		/// Sponsor can't afford the fee budget.
+		InsufficientBudget,
+		/// The sponsorship expires in the past.
+		SponsorshipExpired,
+		/// Account has no sponsorship funded.
+		NoSponsorship,
+		/// The player or board is already sponsored by another sponsor.
+		AlreadySponsored,
+		/// The player isn't sponsored by the sender.
+		NotSponsor,
+		/// Player is already waiting in the matchmaking queue.
+		AlreadyQueued,
+		/// Player isn't waiting in the matchmaking queue.
+		NotQueued,
+		/// The matchmaking queue of the game settings is full.
+		QueueFull,
+		/// The turn length is outside of the allowed time controls.
+		InvalidTimeControl,
+		/// The minimum rating of the challenge is above its maximum rating.
+		InvalidRatingRange,
+		/// Player already has an open challenge in the lobby.
+		ChallengeExists,
+		/// The lobby reached the maximum number of open challenges.
+		LobbyFull,
+		/// Account has no open challenge in the lobby.
+		NoChallenge,
+		/// The challenge expires in the past or already expired.
+		ChallengeExpired,
+		/// The rating of the sender is outside of the rating range of the challenge.
+		RatingOutOfRange,
+		/// Player is banned from starting boards for repeated timeouts.
+		PlayerBanned,
+		/// The opponent blocked the sender from challenging them.
+		BlockedByOpponent,
+		/// The account is already blocked by the sender.
+		AlreadyBlocked,
+		/// The account isn't blocked by the sender.
+		NotBlocked,
+		/// The challenge is only open to friends of its creator.
+		NotFriend,
+		/// The account is already on the friends list of the sender.
+		AlreadyFriend,
+		/// The friends list of the sender is full.
+		TooManyFriends,
 	}
 
 	// Pallet implements [`Hooks`] trait to define some logic to execute in some context.
@@ -203,10 +893,18 @@ pub mod pallet {
 		// dispatched.
 		//
 		// This function must return the weight consumed by `on_initialize` and `on_finalize`.
-		fn on_initialize(_: T::BlockNumber) -> Weight {
-			// Anything that needs to be done at the start of the block.
-			// We don't do anything here.
-			0
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			// Start the next season once the current one passed.
+			let weight = Self::rollover_season(now);
+
+			// Handle the boards whose turn or cleanup deadline expired.
+			let weight = weight.saturating_add(Self::process_deadlines(now));
+
+			// Pair the waiting players whose ratings are close enough.
+			let weight = weight.saturating_add(Self::process_queues(now));
+
+			// Remove the open challenges nobody accepted in time.
+			weight.saturating_add(Self::process_lobby(now))
 		}
 
 		// `on_finalize` is executed at the end of block after all extrinsic are dispatched.
@@ -274,251 +972,2054 @@ pub mod pallet {
 			}
 		}
 
-		/// Create game for two players
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1))]
-		pub fn new_game(origin: OriginFor<T>, opponent: T::AccountId) -> DispatchResult {
+		/// Create game for two players, a non zero stake makes it a wagered game. Each player puts
+		/// up the stake when committing to the coin toss, the winner receives the opponent's stake.
+		/// Stakes in an asset are escrowed and need to reach the minimum stake of the asset.
+		#[pallet::weight(50_000 + T::DbWeight::get().reads_writes(13,15))]
+		pub fn new_game(
+			origin: OriginFor<T>,
+			opponent: T::AccountId,
+			#[pallet::compact] stake: BalanceOf<T>,
+			asset: Option<T::AssetId>
+		) -> DispatchResult {
 			
 			let sender = ensure_signed(origin)?;
 
 			// Don't allow playing against yourself.
 			ensure!(sender != opponent, Error::<T>::NoFakePlay);
 
-			// Make sure players have no board open.
-			ensure!(!PlayerBoard::<T>::contains_key(&sender), Error::<T>::PlayerBoardExists);
-			ensure!(!PlayerBoard::<T>::contains_key(&opponent), Error::<T>::PlayerBoardExists);
-			
+			// Respect the block list of the opponent.
+			ensure!(!Self::blocked(&opponent, &sender), Error::<T>::BlockedByOpponent);
+
+			// Make sure the asset can be wagered in.
+			Self::ensure_stake(asset, stake)?;
+
 			// Create new game
-			let board_id = Self::create_game(sender.clone(), opponent.clone());
+			let board_id = Self::open_board(sender, opponent, GameSettings::default())?;
 
-			// Add board to the players playing it.
-			<PlayerBoard<T>>::insert(sender, board_id);
-			<PlayerBoard<T>>::insert(opponent, board_id);
+			if !stake.is_zero() {
+				<Wagers<T>>::insert(board_id, Wager { asset, stake });
+			}
 
 			Ok(())
 		}
 
-		/// Create game for two players
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1))]
-		pub fn play_turn(origin: OriginFor<T>, column: u8) -> DispatchResult {
-			
+		/// Wait in the matchmaking queue of the game settings for an opponent with a close
+		/// rating, a board gets created once paired.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,2))]
+		pub fn queue(origin: OriginFor<T>, settings: GameSettings) -> DispatchResult {
+
 			let sender = ensure_signed(origin)?;
 
-			ensure!(column < 8, "Game only allows columns smaller then 8");
+			ensure!(
+				settings.turn_blocks >= MIN_BLOCKS_PER_TURN && settings.turn_blocks <= MAX_BLOCKS_PER_TURN,
+				Error::<T>::InvalidTimeControl
+			);
 
-			// TODO: should PlayerBoard storage here be optional to avoid two reads?
-			ensure!(PlayerBoard::<T>::contains_key(&sender), Error::<T>::NoPlayerBoard);
-			let board_id = Self::player_board(&sender);
-	
-			// Get board from player.
-			ensure!(Boards::<T>::contains_key(&board_id), "No board found");
-			let mut board = Self::boards(&board_id);
-			
-			// Board is still open to play and not finished.
-			ensure!(board.board_state == BoardState::Running, "Board is not running, check if already finished.");
+			// Make sure player has no board open and can afford the deposit once paired.
+			Self::ensure_not_banned(&sender)?;
+			ensure!(!PlayerBoard::<T>::contains_key(&sender), Error::<T>::PlayerBoardExists);
+			ensure!(!QueuedPlayers::<T>::contains_key(&sender), Error::<T>::AlreadyQueued);
+			ensure!(T::Currency::can_reserve(&sender, T::BoardDeposit::get()), Error::<T>::InsufficientDeposit);
 
-			let current_player = board.next_player;
-			let current_account;
-
-			// Check if correct player is at turn
-			if current_player == PLAYER_1 {
-				current_account = board.red.clone();
-				board.next_player = PLAYER_2;
-			} else if current_player == PLAYER_2 {
-				current_account = board.blue.clone();
-				board.next_player = PLAYER_1;
-			} else {
-				return Err(Error::<T>::WrongLogic)?
-			}
+			let entry = QueueEntry {
+				player: sender.clone(),
+				rating: Self::season_rating(&sender).rating,
+				since: <frame_system::Pallet<T>>::block_number(),
+			};
+			<MatchQueues<T>>::try_mutate(settings, |queue| queue.try_push(entry))
+				.map_err(|_| Error::<T>::QueueFull)?;
+			<QueuedPlayers<T>>::insert(&sender, settings);
 
-			// Make sure current account is at turn.
-			ensure!(sender == current_account, Error::<T>::NotPlayerTurn);
+			Self::deposit_event(Event::Queued(sender));
 
-			// Check if we can successfully place a stone in that column
-			if !Logic::add_stone(&mut board.board, column, current_player) {
-				return Err(Error::<T>::WrongLogic)?
-			}
+			Ok(())
+		}
 
-			// Check if the last played stone gave us a winner or board is full
-			if Logic::evaluate(board.board.clone(), current_player) {
-				board.board_state = BoardState::Finished(current_account);
-			} else if Logic::full(board.board.clone()) {
-				board.board_state = BoardState::Finished(Default::default());
-			}
+		/// Leave the matchmaking queue.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,2))]
+		pub fn dequeue(origin: OriginFor<T>) -> DispatchResult {
 
-			// get current blocknumber
-			let last_turn = <frame_system::Pallet<T>>::block_number();
-			board.last_turn = last_turn;
+			let sender = ensure_signed(origin)?;
 
-			// Write next board state back into the storage
-			<Boards<T>>::insert(board_id, board);
+			ensure!(Self::leave_queue(&sender), Error::<T>::NotQueued);
 
-			// Cancel scheduled task
-			if BoardSchedules::<T>::contains_key(&board_id) {
-				let old_schedule_id = Self::board_schedules(&board_id);
-				if old_schedule_id.is_some() {
-					// cancel scheduled force end turn
-					if T::Scheduler::cancel_named(
-						old_schedule_id.unwrap(),
-					).is_err() {
-						frame_support::print("LOGIC ERROR: test_schedule/schedule_named failed");
-					}
-				}
-			}
+			Self::deposit_event(Event::Dequeued(sender));
+
+			Ok(())
+		}
+
+		/// Post an open challenge to the lobby, any account within the rating range, or only the
+		/// friends of the sender, can accept it until the given block.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,1))]
+		pub fn post_challenge(
+			origin: OriginFor<T>,
+			settings: GameSettings,
+			#[pallet::compact] stake: BalanceOf<T>,
+			asset: Option<T::AssetId>,
+			rating_range: (u32, u32),
+			friends_only: bool,
+			expires: T::BlockNumber
+		) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
 
-			let schedule_id = Self::schedule_end_turn(board_id, last_turn, last_turn + MAX_BLOCKS_PER_TURN.into());
-			<BoardSchedules<T>>::insert(board_id, schedule_id);
+			ensure!(
+				settings.turn_blocks >= MIN_BLOCKS_PER_TURN && settings.turn_blocks <= MAX_BLOCKS_PER_TURN,
+				Error::<T>::InvalidTimeControl
+			);
+			Self::ensure_stake(asset, stake)?;
+			let (min_rating, max_rating) = rating_range;
+			ensure!(min_rating <= max_rating, Error::<T>::InvalidRatingRange);
+			ensure!(expires > <frame_system::Pallet<T>>::block_number(), Error::<T>::ChallengeExpired);
+
+			// Make sure player has no board open and can afford the deposit once accepted.
+			Self::ensure_not_banned(&sender)?;
+			ensure!(!PlayerBoard::<T>::contains_key(&sender), Error::<T>::PlayerBoardExists);
+			ensure!(T::Currency::can_reserve(&sender, T::BoardDeposit::get()), Error::<T>::InsufficientDeposit);
+
+			let mut lobby = Self::lobby();
+			ensure!(!lobby.iter().any(|challenge| challenge.creator == sender), Error::<T>::ChallengeExists);
+			lobby.try_push(Challenge {
+				creator: sender.clone(),
+				settings,
+				asset,
+				stake,
+				min_rating,
+				max_rating,
+				friends_only,
+				expires,
+			}).map_err(|_| Error::<T>::LobbyFull)?;
+			<Lobby<T>>::put(lobby);
+
+			Self::deposit_event(Event::ChallengePosted(sender));
 
 			Ok(())
 		}
 
-		/// An example dispatchable that may throw a custom error.
+		/// Remove the open challenge of the sender from the lobby.
 		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1))]
-		pub fn test_schedule(origin: OriginFor<T>, delay: T::BlockNumber) -> DispatchResult {
+		pub fn cancel_challenge(origin: OriginFor<T>) -> DispatchResult {
 
 			let sender = ensure_signed(origin)?;
 
-			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(Self::withdraw_challenge(&sender).is_some(), Error::<T>::NoChallenge);
 
-			let index: u32 = 77;
-			let when = now + delay;
-			if T::Scheduler::schedule_named(
-				(CONNECTFOUR_ID, index).encode(),
-				DispatchTime::At(when),
-				None,
-				63,
-				frame_system::RawOrigin::Signed(sender).into(),
-				Call::do_something(index).into(),
-			).is_err() {
-				frame_support::print("LOGIC ERROR: test_schedule/schedule_named failed");
-				return Err(Error::<T>::ScheduleError)?;
-			}
+			Self::deposit_event(Event::ChallengeCancelled(sender));
 
 			Ok(())
 		}
 
-		/// Force end turn after max blocks per turn passed.
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1))]
-		fn force_end_turn(origin: OriginFor<T>, board_id: T::Hash, last_turn: T::BlockNumber) -> DispatchResult {
-			ensure_root(origin)?;
+		/// Accept the open challenge of a creator, the board starts right away.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(8,14))]
+		pub fn accept_challenge(origin: OriginFor<T>, creator: T::AccountId) -> DispatchResult {
 
-			// Get board from player.
-			ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
-			let mut board = Self::boards(&board_id);
+			let sender = ensure_signed(origin)?;
 
-			ensure!(board.last_turn == last_turn, "There has been a move in between.");
+			ensure!(sender != creator, Error::<T>::NoFakePlay);
 
-			if board.board_state == BoardState::Running {
+			let challenge = Self::lobby().iter()
+				.find(|challenge| challenge.creator == creator)
+				.cloned()
+				.ok_or(Error::<T>::NoChallenge)?;
+			ensure!(challenge.expires > <frame_system::Pallet<T>>::block_number(), Error::<T>::ChallengeExpired);
 
-				if board.next_player == PLAYER_1 {
-					board.board_state = BoardState::Finished(board.blue.clone());
-				} else if board.next_player == PLAYER_2 {
-					board.board_state = BoardState::Finished(board.red.clone());
-				} else {
-					return Err(Error::<T>::WrongLogic)?
-				}
+			ensure!(!Self::blocked(&creator, &sender), Error::<T>::BlockedByOpponent);
+			ensure!(
+				!challenge.friends_only || Self::friends(&creator).contains(&sender),
+				Error::<T>::NotFriend
+			);
 
-				// get current blocknumber
-				let last_turn = <frame_system::Pallet<T>>::block_number();
-				board.last_turn = last_turn;
-				
-				// Write next board state back into the storage
-				<Boards<T>>::insert(board_id, board);
+			let rating = Self::season_rating(&sender).rating;
+			ensure!(
+				rating >= challenge.min_rating && rating <= challenge.max_rating,
+				Error::<T>::RatingOutOfRange
+			);
 
-				// Execute cleanup task
-				let schedule_id = Self::schedule_end_turn(board_id, last_turn, last_turn + CLEANUP_BOARDS_AFTER.into());
-				<BoardSchedules<T>>::insert(board_id, schedule_id);
-			
-			} else {
+			// Starting the board removes the challenge from the lobby.
+			let board_id = Self::open_board(creator.clone(), sender.clone(), challenge.settings)?;
 
-				// do cleanup after final force turn.
-				<Boards<T>>::remove(board_id);
-				<PlayerBoard<T>>::remove(board.red);
-				<PlayerBoard<T>>::remove(board.blue);
-				<BoardSchedules<T>>::remove(board_id);
+			if !challenge.stake.is_zero() {
+				<Wagers<T>>::insert(board_id, Wager { asset: challenge.asset, stake: challenge.stake });
 			}
 
+			Self::deposit_event(Event::ChallengeAccepted(creator, sender, board_id));
+
+			Ok(())
+		}
+
+		/// Block an account from challenging the sender, blocked accounts are no friends.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,2))]
+		pub fn block_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(sender != who, Error::<T>::NoFakePlay);
+			ensure!(!Self::blocked(&sender, &who), Error::<T>::AlreadyBlocked);
+
+			<BlockedAccounts<T>>::insert(&sender, &who, true);
+			Self::unfriend(&sender, &who);
+
+			Self::deposit_event(Event::AccountBlocked(sender, who));
+
+			Ok(())
+		}
+
+		/// Allow a blocked account to challenge the sender again.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1))]
+		pub fn unblock_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::blocked(&sender, &who), Error::<T>::NotBlocked);
+
+			<BlockedAccounts<T>>::remove(&sender, &who);
+
+			Self::deposit_event(Event::AccountUnblocked(sender, who));
+
+			Ok(())
+		}
+
+		/// Add an account to the friends list of the sender.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,1))]
+		pub fn add_friend(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(sender != who, Error::<T>::NoFakePlay);
+			ensure!(!Self::blocked(&sender, &who), Error::<T>::AlreadyBlocked);
+
+			let mut friends = Self::friends(&sender);
+			ensure!(!friends.contains(&who), Error::<T>::AlreadyFriend);
+			friends.try_push(who.clone()).map_err(|_| Error::<T>::TooManyFriends)?;
+			<Friends<T>>::insert(&sender, friends);
+
+			Self::deposit_event(Event::FriendAdded(sender, who));
+
+			Ok(())
+		}
+
+		/// Remove an account from the friends list of the sender.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1))]
+		pub fn remove_friend(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::unfriend(&sender, &who), Error::<T>::NotFriend);
+
+			Self::deposit_event(Event::FriendRemoved(sender, who));
+
 			Ok(())
 		}
+
+		/// Place a stone in a column of the sender's board, legal moves don't pay fees. The
+		/// weight covers finishing the board, moves that don't finish it refund the difference.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1)
+			.saturating_add(Pallet::<T>::finish_weight(T::MaxBettorsPerBoard::get())))]
+		pub fn play_turn(origin: OriginFor<T>, column: u8) -> DispatchResultWithPostInfo {
+			
+			let sender = ensure_signed(origin)?;
+
+			// Session keys move on behalf of the player who authorised them.
+			let player = Self::move_player(&sender);
+
+			// Get board from player and make sure the move is legal.
+			let (board_id, mut board) = Self::validate_move(&player, column)?;
+
+			let current_player = board.next_player;
+			let current_account = player;
+
+			// Hand the turn over to the opponent
+			board.next_player = if current_player == PLAYER_1 { PLAYER_2 } else { PLAYER_1 };
+
+			// Check if we can successfully place a stone in that column
+			if !Logic::add_stone(&mut board.board, column, current_player) {
+				return Err(Error::<T>::WrongLogic)?
+			}
+
+			let mut weight = 10_000 + T::DbWeight::get().reads_writes(1,1);
+
+			// Check if the last played stone gave us a winner or board is full
+			if Logic::evaluate(board.board.clone(), current_player) {
+				weight = weight.saturating_add(Self::finish_weight(Self::market_bettors(&board_id)));
+				Self::finish_board(board_id, board, BoardState::Finished(current_account), EndReason::Played);
+			} else if Logic::full(board.board.clone()) {
+				weight = weight.saturating_add(Self::finish_weight(Self::market_bettors(&board_id)));
+				Self::finish_board(board_id, board, BoardState::Finished(Default::default()), EndReason::Played);
+			} else {
+				// get current blocknumber
+				let last_turn = <frame_system::Pallet<T>>::block_number();
+				board.last_turn = last_turn;
+
+				// Write next board state back into the storage
+				<Boards<T>>::insert(board_id, board);
+
+				// Replace the deadline of the previous turn
+				Self::schedule_deadline(board_id, last_turn + Self::turn_length(board_id));
+			}
+
+			// Legal moves are free, spam is kept off by the board deposit and move pre-validation.
+			Ok(PostDispatchInfo { actual_weight: Some(weight), pays_fee: Pays::No })
+		}
+
+		/// Resign the running board of the sender, the opponent wins.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,5)
+			.saturating_add(Pallet::<T>::finish_weight(T::MaxBettorsPerBoard::get())))]
+		pub fn resign(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(PlayerBoard::<T>::contains_key(&sender), Error::<T>::NoPlayerBoard);
+			let board_id = Self::player_board(&sender);
+
+			ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
+			let board = Self::boards(&board_id);
+
+			// Board is still open to play and not finished.
+			ensure!(board.board_state == BoardState::Running, "Board is not running, check if already finished.");
+
+			let winner = if sender == board.red { board.blue.clone() } else { board.red.clone() };
+			let weight = Self::finish_weight(Self::market_bettors(&board_id));
+
+			Self::finish_board(board_id, board, BoardState::Finished(winner), EndReason::Resigned);
+
+			Ok(Some(weight.saturating_add(10_000 + T::DbWeight::get().reads_writes(3,5))).into())
+		}
+
+		/// Claim the win after the opponent let the turn deadline pass.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,3)
+			.saturating_add(Pallet::<T>::finish_weight(T::MaxBettorsPerBoard::get())))]
+		pub fn claim_timeout(
+			origin: OriginFor<T>,
+			board_id: T::Hash,
+			last_turn: T::BlockNumber
+		) -> DispatchResultWithPostInfo {
+
+			let sender = ensure_signed(origin)?;
+
+			// Get board from player.
+			ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
+			let board = Self::boards(&board_id);
+
+			ensure!(board.last_turn == last_turn, "There has been a move in between.");
+
+			// Board is still open to play and not finished.
+			ensure!(board.board_state == BoardState::Running, "Board is not running, check if already finished.");
+
+			// Make sure the sender is waiting for the opponent to play.
+			let waiting_account = Self::timeout_winner(board_id, &board)?;
+			ensure!(sender == waiting_account, Error::<T>::NotWaitingPlayer);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(now >= last_turn + Self::turn_length(board_id), Error::<T>::TurnNotExpired);
+
+			let weight = Self::finish_weight(Self::market_bettors(&board_id));
+			Self::end_turn(board_id)?;
+
+			Ok(Some(weight.saturating_add(10_000 + T::DbWeight::get().reads_writes(2,3))).into())
+		}
+
+		/// Commit to the coin toss of the sender's board with the hash of the sender's account
+		/// and a secret.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,4))]
+		pub fn commit_toss(origin: OriginFor<T>, commitment: T::Hash) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(PlayerBoard::<T>::contains_key(&sender), Error::<T>::NoPlayerBoard);
+			let board_id = Self::player_board(&sender);
+			let mut toss = Self::coin_tosses(&board_id).ok_or(Error::<T>::NoCoinToss)?;
+			let mut board = Self::boards(&board_id);
+
+			let commit = if sender == board.red {
+				&mut toss.red_commit
+			} else {
+				&mut toss.blue_commit
+			};
+			ensure!(commit.is_none(), Error::<T>::AlreadyCommitted);
+
+			// Committing to the board reserves the deposit of the opponent.
+			let deposit = if BoardDeposits::<T>::contains_key(board_id, &sender) {
+				Zero::zero()
+			} else {
+				T::BoardDeposit::get()
+			};
+			ensure!(T::Currency::can_reserve(&sender, deposit), Error::<T>::InsufficientDeposit);
+
+			// Committing to play a wagered board reserves or escrows the stake.
+			Self::place_stake(board_id, &sender, deposit)?;
+			if !deposit.is_zero() {
+				T::Currency::reserve(&sender, deposit)?;
+				<BoardDeposits<T>>::insert(board_id, &sender, deposit);
+			}
+			*commit = Some(commitment);
+
+			// Once both committed, the reveal phase gets its own deadline.
+			if toss.committed() {
+				let last_turn = <frame_system::Pallet<T>>::block_number();
+				board.last_turn = last_turn;
+				<Boards<T>>::insert(board_id, board);
+				Self::schedule_deadline(board_id, last_turn + Self::turn_length(board_id));
+			}
+			<CoinTosses<T>>::insert(board_id, toss);
+
+			Self::deposit_event(Event::TossCommitted(board_id, sender));
+
+			Ok(())
+		}
+
+		/// Reveal the secret committed to the coin toss of the sender's board. The starting
+		/// player is decided as soon as both secrets are revealed.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,4))]
+		pub fn reveal_toss(origin: OriginFor<T>, secret: T::Hash) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(PlayerBoard::<T>::contains_key(&sender), Error::<T>::NoPlayerBoard);
+			let board_id = Self::player_board(&sender);
+			let mut toss = Self::coin_tosses(&board_id).ok_or(Error::<T>::NoCoinToss)?;
+			ensure!(toss.committed(), Error::<T>::TossNotCommitted);
+			let mut board = Self::boards(&board_id);
+
+			let (commit, revealed) = if sender == board.red {
+				(toss.red_commit, &mut toss.red_secret)
+			} else {
+				(toss.blue_commit, &mut toss.blue_secret)
+			};
+			ensure!(revealed.is_none(), Error::<T>::AlreadyRevealed);
+			ensure!(commit == Some(T::Hashing::hash_of(&(&sender, secret))), Error::<T>::InvalidReveal);
+			*revealed = Some(secret);
+
+			match (toss.red_secret, toss.blue_secret) {
+				(Some(red_secret), Some(blue_secret)) => {
+					// calculate player to start the first turn, with the first byte of the combined secrets
+					let coin = (red_secret, blue_secret).using_encoded(T::Hashing::hash);
+					let (next_player, starting_account) = if coin.as_ref()[0] < 128 {
+						(PLAYER_1, board.red.clone())
+					} else {
+						(PLAYER_2, board.blue.clone())
+					};
+
+					let last_turn = <frame_system::Pallet<T>>::block_number();
+					board.next_player = next_player;
+					board.last_turn = last_turn;
+					<Boards<T>>::insert(board_id, board);
+					<CoinTosses<T>>::remove(board_id);
+					Self::schedule_deadline(board_id, last_turn + Self::turn_length(board_id));
+
+					Self::deposit_event(Event::TossDecided(board_id, starting_account));
+				},
+				_ => <CoinTosses<T>>::insert(board_id, toss),
+			}
+
+			Ok(())
+		}
+
+		/// Authorise a session key to place moves on the sender's current board, optionally only
+		/// until the given block. A previously authorised key gets replaced.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,3))]
+		pub fn authorize_move_key(origin: OriginFor<T>, key: T::AccountId, expires: Option<T::BlockNumber>) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(PlayerBoard::<T>::contains_key(&sender), Error::<T>::NoPlayerBoard);
+			let board_id = Self::player_board(&sender);
+
+			// Players can't act for each other and keys only serve one player.
+			ensure!(!PlayerBoard::<T>::contains_key(&key), Error::<T>::MoveKeyInUse);
+			ensure!(!MoveKeys::<T>::contains_key(&key), Error::<T>::MoveKeyInUse);
+
+			if let Some(old_key) = <PlayerMoveKey<T>>::take(&sender) {
+				<MoveKeys<T>>::remove(old_key);
+			}
+			<MoveKeys<T>>::insert(&key, MoveKey { owner: sender.clone(), board_id, expires });
+			<PlayerMoveKey<T>>::insert(&sender, &key);
+
+			Self::deposit_event(Event::MoveKeyAuthorized(board_id, sender, key));
+
+			Ok(())
+		}
+
+		/// Revoke the session key of the sender.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,2))]
+		pub fn revoke_move_key(origin: OriginFor<T>) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			let key = <PlayerMoveKey<T>>::take(&sender).ok_or(Error::<T>::NoMoveKey)?;
+			<MoveKeys<T>>::remove(&key);
+
+			Self::deposit_event(Event::MoveKeyRevoked(sender, key));
+
+			Ok(())
+		}
+
+		/// Back a player of a running board, the funds stay reserved until the game is finished.
+		/// Bets are accepted until the market closing block or move, the backers of the winner
+		/// share the pool of the loser in proportion to their bets.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,2))]
+		pub fn place_bet(
+			origin: OriginFor<T>,
+			board_id: T::Hash,
+			player: T::AccountId,
+			#[pallet::compact] amount: BalanceOf<T>
+		) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
+			let board = Self::boards(&board_id);
+
+			// Board is still open to play and not finished.
+			ensure!(board.board_state == BoardState::Running, "Board is not running, check if already finished.");
+
+			ensure!(sender != board.red && sender != board.blue, Error::<T>::PlayerCannotBet);
+			let backs_red = if player == board.red {
+				true
+			} else {
+				ensure!(player == board.blue, Error::<T>::NotBoardPlayer);
+				false
+			};
+			ensure!(amount >= T::MinBet::get(), Error::<T>::BetTooSmall);
+
+			// Make sure the market is still open.
+			let mut market = Self::markets(&board_id).ok_or(Error::<T>::MarketClosed)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(now < market.closes, Error::<T>::MarketClosed);
+			ensure!(Logic::moves(board.board) < T::MarketCloseMoves::get(), Error::<T>::MarketClosed);
+
+			let mut bet = Self::bets(&board_id, &sender);
+			if bet == Bet::default() {
+				ensure!(market.bettors < T::MaxBettorsPerBoard::get(), Error::<T>::TooManyBettors);
+				market.bettors += 1;
+			}
+
+			T::Currency::reserve(&sender, amount).map_err(|_| Error::<T>::InsufficientBalance)?;
+
+			if backs_red {
+				bet.red = bet.red.saturating_add(amount);
+				market.red_pool = market.red_pool.saturating_add(amount);
+			} else {
+				bet.blue = bet.blue.saturating_add(amount);
+				market.blue_pool = market.blue_pool.saturating_add(amount);
+			}
+			<Bets<T>>::insert(board_id, &sender, bet);
+			<Markets<T>>::insert(board_id, market);
+
+			Self::deposit_event(Event::BetPlaced(board_id, sender, player, amount));
+
+			Ok(())
+		}
+
+		/// Reserve a fee budget paying the `new_game` and `play_turn` fees of sponsored players and
+		/// boards until the expiry block, funding again adds to the budget and sets the expiry.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,2))]
+		pub fn fund_sponsorship(
+			origin: OriginFor<T>,
+			#[pallet::compact] amount: BalanceOf<T>,
+			expires: T::BlockNumber
+		) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(expires > now, Error::<T>::SponsorshipExpired);
+
+			T::Currency::reserve(&sender, amount).map_err(|_| Error::<T>::InsufficientBudget)?;
+
+			let mut sponsorship = Self::sponsorships(&sender).unwrap_or_default();
+			sponsorship.budget = sponsorship.budget.saturating_add(amount);
+			sponsorship.expires = expires;
+			<Sponsorships<T>>::insert(&sender, &sponsorship);
+
+			Self::deposit_event(Event::SponsorshipFunded(sender, sponsorship.budget, expires));
+
+			Ok(())
+		}
+
+		/// Close the fee budget of the sender and return the rest of it.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,2))]
+		pub fn close_sponsorship(origin: OriginFor<T>) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			let sponsorship = <Sponsorships<T>>::take(&sender).ok_or(Error::<T>::NoSponsorship)?;
+			T::Currency::unreserve(&sender, sponsorship.budget);
+
+			Self::deposit_event(Event::SponsorshipClosed(sender, sponsorship.budget));
+
+			Ok(())
+		}
+
+		/// Pay the fees of a player from the sender's budget.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,1))]
+		pub fn sponsor_player(origin: OriginFor<T>, player: T::AccountId) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Sponsorships::<T>::contains_key(&sender), Error::<T>::NoSponsorship);
+			if let Some(sponsor) = Self::sponsored_players(&player) {
+				ensure!(sponsor == sender || !Sponsorships::<T>::contains_key(&sponsor), Error::<T>::AlreadySponsored);
+			}
+
+			<SponsoredPlayers<T>>::insert(&player, &sender);
+
+			Self::deposit_event(Event::PlayerSponsored(sender, player));
+
+			Ok(())
+		}
+
+		/// Stop paying the fees of a player.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1))]
+		pub fn unsponsor_player(origin: OriginFor<T>, player: T::AccountId) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::sponsored_players(&player) == Some(sender.clone()), Error::<T>::NotSponsor);
+
+			<SponsoredPlayers<T>>::remove(&player);
+
+			Self::deposit_event(Event::PlayerUnsponsored(sender, player));
+
+			Ok(())
+		}
+
+		/// Pay the fees of the moves on a board from the sender's budget.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,1))]
+		pub fn sponsor_board(origin: OriginFor<T>, board_id: T::Hash) -> DispatchResult {
+
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Sponsorships::<T>::contains_key(&sender), Error::<T>::NoSponsorship);
+			ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
+			if let Some(sponsor) = Self::sponsored_boards(&board_id) {
+				ensure!(sponsor == sender || !Sponsorships::<T>::contains_key(&sponsor), Error::<T>::AlreadySponsored);
+			}
+
+			<SponsoredBoards<T>>::insert(board_id, &sender);
+
+			Self::deposit_event(Event::BoardSponsored(sender, board_id));
+
+			Ok(())
+		}
+
+		/// Set the minimum stake of an asset boards can be wagered in, none disallows the asset.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_min_stake(origin: OriginFor<T>, asset: T::AssetId, min_stake: Option<BalanceOf<T>>) -> DispatchResult {
+			ensure_root(origin)?;
+
+			<MinStakes<T>>::set(asset, min_stake);
+
+			Self::deposit_event(Event::MinStakeSet(asset, min_stake));
+
+			Ok(())
+		}
+
+		/// Rotate the founder key receiving the platform rake.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_founder_key(origin: OriginFor<T>, founder_key: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+
+			<FounderKey<T>>::put(&founder_key);
+
+			Self::deposit_event(Event::FounderKeyChanged(founder_key));
+
+			Ok(())
+		}
+
+		/// Force end turn after max blocks per turn passed, slashing the player who stalled.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,5)
+			.saturating_add(Pallet::<T>::finish_weight(T::MaxBettorsPerBoard::get())))]
+		pub fn force_end_turn(
+			origin: OriginFor<T>,
+			board_id: T::Hash,
+			last_turn: T::BlockNumber
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			// Get board from player.
+			ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
+			let board = Self::boards(&board_id);
+
+			ensure!(board.last_turn == last_turn, "There has been a move in between.");
+
+			let weight = Self::finish_weight(Self::market_bettors(&board_id));
+			Self::end_turn(board_id)?;
+
+			Ok(Some(weight.saturating_add(10_000 + T::DbWeight::get().reads_writes(3,5))).into())
+		}
+
+		/// Void a board without a result, the deposits are returned to both players.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,6))]
+		pub fn void_game(origin: OriginFor<T>, board_id: T::Hash) -> DispatchResult {
+			ensure_root(origin)?;
+
+			ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
+			let board = Self::boards(&board_id);
+
+			Self::void_board(board_id, board);
+
+			Ok(())
+		}
+
+		/// Finish a running board with the given winner, or as a draw if there is none.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,3)
+			.saturating_add(Pallet::<T>::finish_weight(T::MaxBettorsPerBoard::get())))]
+		pub fn force_finish(
+			origin: OriginFor<T>,
+			board_id: T::Hash,
+			winner: Option<T::AccountId>
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+
+			ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
+			let board = Self::boards(&board_id);
+
+			// Board is still open to play and not finished.
+			ensure!(board.board_state == BoardState::Running, "Board is not running, check if already finished.");
+
+			let board_state = match &winner {
+				Some(account) => {
+					ensure!(*account == board.red || *account == board.blue, Error::<T>::NotBoardPlayer);
+					BoardState::Finished(account.clone())
+				},
+				None => BoardState::Finished(Default::default()),
+			};
+			let weight = Self::finish_weight(Self::market_bettors(&board_id));
+			Self::finish_board(board_id, board, board_state, EndReason::Forced);
+
+			Self::deposit_event(Event::GameForceFinished(board_id, winner));
+
+			Ok(Some(weight.saturating_add(10_000 + T::DbWeight::get().reads_writes(1,3))).into())
+		}
+
+		/// Purge player boards and the storage of boards that don't exist anymore. Reserved
+		/// deposits, stakes and bets left on a purged board are returned.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			2 * players.len() as Weight + 10 * boards.len() as Weight,
+			2 * players.len() as Weight + 10 * boards.len() as Weight,
+		).saturating_add(T::DbWeight::get().reads_writes(1, 2).saturating_mul(
+			boards.len() as Weight * (T::MaxBettorsPerBoard::get() as Weight + 2)
+		)))]
+		pub fn purge_orphans(origin: OriginFor<T>, players: Vec<T::AccountId>, boards: Vec<T::Hash>) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let mut purged_players: u32 = 0;
+			for player in players {
+				if PlayerBoard::<T>::contains_key(&player) && !Boards::<T>::contains_key(Self::player_board(&player)) {
+					<PlayerBoard<T>>::remove(player);
+					purged_players += 1;
+				}
+			}
+
+			let mut purged_boards: u32 = 0;
+			for board_id in boards {
+				if Boards::<T>::contains_key(&board_id) {
+					continue
+				}
+				let mut purged = false;
+				for (player, deposit) in <BoardDeposits<T>>::drain_prefix(board_id) {
+					T::Currency::unreserve(&player, deposit);
+					purged = true;
+				}
+				if Self::clear_board(board_id) || purged {
+					purged_boards += 1;
+				}
+			}
+
+			Self::deposit_event(Event::OrphansPurged(purged_players, purged_boards));
+
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+
+	/// The id of the next board created by an account, clients can derive the same id from the
+	/// account and its board nonce.
+	pub fn next_board_id(
+		creator: &T::AccountId
+	) -> T::Hash {
+		(CONNECTFOUR_ID, creator, Self::board_nonce(creator)).using_encoded(T::Hashing::hash)
+	}
+
+	/// Generate a new game between two players.
+	fn create_game(
+		red: T::AccountId, 
+		blue: T::AccountId,
+		settings: GameSettings
+	) -> Result<T::Hash, DispatchError> {
+		// get the board id derived from the creator
+		let board_id = Self::next_board_id(&red);
+		// never overwrite a live board
+		ensure!(!Boards::<T>::contains_key(&board_id), Error::<T>::BoardExists);
+		// get the next sequential game number
+		let game_number = Self::game_count().checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
+		// get current blocknumber
+		let block_number = <frame_system::Pallet<T>>::block_number();
+		// create a new empty bgame oard
+		let board = BoardStruct {
+			id: board_id,
+			red: red,
+			blue: blue,
+			board: [[0u8; 6]; 7],
+			last_turn: block_number,
+			// the starting player gets decided by the coin toss
+			next_player: 0,
+			board_state: BoardState::Running,
+		};
+		// insert the new board into the storage
+		<Boards<T>>::insert(board_id, board);
+		<CoinTosses<T>>::insert(board_id, CoinToss::default());
+		if settings != GameSettings::default() {
+			<BoardSettings<T>>::insert(board_id, settings);
+		}
+		<Markets<T>>::insert(board_id, Market {
+			closes: block_number + T::MarketPeriod::get(),
+			..Default::default()
+		});
+		<BoardNonce<T>>::mutate(&red, |nonce| *nonce = nonce.wrapping_add(1));
+		<GameCount<T>>::put(game_number);
+		<BoardNumbers<T>>::insert(board_id, game_number);
+		<GameNumbers<T>>::insert(game_number, board_id);
+		// both players have to commit before the turn deadline
+		Self::schedule_deadline(board_id, block_number + settings.turn_blocks.into());
+		// emit event for a new board creation
+		// Emit an event.
+		Self::deposit_event(Event::NewBoard(board_id, game_number));
+
+		Ok(board_id)
+	}
+
+	/// Create a board between two players without open boards, reserving the board deposit of
+	/// the creator.
+	fn open_board(
+		red: T::AccountId,
+		blue: T::AccountId,
+		settings: GameSettings
+	) -> Result<T::Hash, DispatchError> {
+		// Players stalling too often sit out their ban.
+		Self::ensure_not_banned(&red)?;
+		Self::ensure_not_banned(&blue)?;
+
+		// Make sure players have no board open.
+		ensure!(!PlayerBoard::<T>::contains_key(&red), Error::<T>::PlayerBoardExists);
+		ensure!(!PlayerBoard::<T>::contains_key(&blue), Error::<T>::PlayerBoardExists);
+
+		// Make sure the creator can afford the board deposit, the opponent reserves theirs when
+		// committing to the board.
+		let deposit = T::BoardDeposit::get();
+		ensure!(T::Currency::can_reserve(&red, deposit), Error::<T>::InsufficientDeposit);
+
+		let board_id = Self::create_game(red.clone(), blue.clone(), settings)?;
+
+		T::Currency::reserve(&red, deposit)?;
+		<BoardDeposits<T>>::insert(board_id, &red, deposit);
+
+		// Players starting a board stop waiting for another one.
+		Self::leave_queue(&red);
+		Self::leave_queue(&blue);
+		Self::withdraw_challenge(&red);
+		Self::withdraw_challenge(&blue);
+
+		// Add board to the players playing it.
+		<PlayerBoard<T>>::insert(red, board_id);
+		<PlayerBoard<T>>::insert(blue, board_id);
+
+		Ok(board_id)
+	}
+
+	/// Remove a player from the matchmaking queue they wait in, returns if they were queued.
+	fn leave_queue(
+		who: &T::AccountId
+	) -> bool {
+		match <QueuedPlayers<T>>::take(who) {
+			Some(settings) => {
+				let mut queue = Self::match_queues(settings);
+				queue.retain(|entry| entry.player != *who);
+				if queue.is_empty() {
+					<MatchQueues<T>>::remove(settings);
+				} else {
+					<MatchQueues<T>>::insert(settings, queue);
+				}
+				true
+			},
+			None => false,
+		}
+	}
+
+	/// Rating difference a waiting player accepts an opponent within, widening while they wait.
+	fn match_window(
+		entry: &QueueEntryOf<T>,
+		now: T::BlockNumber
+	) -> u32 {
+		let waited: u32 = now.saturating_sub(entry.since).saturated_into();
+		T::MatchWindow::get().saturating_add(T::MatchWindowStep::get().saturating_mul(waited))
+	}
+
+	/// The game settings of all matchmaking queues, in the order the matchmaking scans them.
+	fn queue_settings() -> Vec<GameSettings> {
+		[GameMode::Ranked, GameMode::Casual].iter()
+			.flat_map(|mode| (MIN_BLOCKS_PER_TURN..=MAX_BLOCKS_PER_TURN)
+				.map(move |turn_blocks| GameSettings { mode: *mode, turn_blocks }))
+			.collect()
+	}
+
+	/// Pair the longest waiting players of each queue with the first opponent both of their
+	/// windows accept and neither blocked, within the per block budgets. Players who can't afford
+	/// the board deposit anymore get dropped from the queue. The scan stops once the comparisons
+	/// of a block are used up and resumes from the cursor in the next block.
+	fn process_queues(
+		now: T::BlockNumber
+	) -> Weight {
+		let all_settings = Self::queue_settings();
+		let (queue_index, index, other) = Self::queue_cursor();
+		let mut queue_index = queue_index as usize % all_settings.len();
+		let (mut index, mut other) = (index as usize, other as usize);
+		let mut weight = T::DbWeight::get().reads_writes(1, 1);
+
+		let deposit = T::BoardDeposit::get();
+		let max_matches = T::MaxMatchesPerBlock::get();
+		let mut matches = 0;
+		let mut comparisons = T::MaxQueueComparisons::get();
+
+		// Visit every queue once, starting with the one the previous block stopped in.
+		for _ in 0..all_settings.len() {
+			if matches >= max_matches || comparisons == 0 {
+				break
+			}
+			let settings = all_settings[queue_index];
+			let mut queue = Self::match_queues(settings);
+			let waiting = queue.len();
+			weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+			while index < queue.len() && matches < max_matches && comparisons > 0 {
+				// The waiting player is checked before their first comparison.
+				if other <= index {
+					comparisons -= 1;
+					weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+					if !T::Currency::can_reserve(&queue[index].player, deposit) {
+						let entry = queue.remove(index);
+						<QueuedPlayers<T>>::remove(&entry.player);
+						Self::deposit_event(Event::Dequeued(entry.player));
+						continue
+					}
+					other = index + 1;
+				}
+
+				let window = Self::match_window(&queue[index], now);
+				let rating = queue[index].rating;
+				let mut opponent = None;
+				while other < queue.len() && comparisons > 0 {
+					comparisons -= 1;
+					weight = weight.saturating_add(T::DbWeight::get().reads(3));
+
+					let candidate = &queue[other];
+					let diff = if rating > candidate.rating { rating - candidate.rating } else { candidate.rating - rating };
+					if diff <= window && diff <= Self::match_window(candidate, now) &&
+						!Self::blocked(&queue[index].player, &candidate.player) &&
+						!Self::blocked(&candidate.player, &queue[index].player) &&
+						T::Currency::can_reserve(&candidate.player, deposit) {
+						opponent = Some(other);
+						break
+					}
+					other += 1;
+				}
+
+				match opponent {
+					Some(position) => {
+						let blue = queue.remove(position);
+						let red = queue.remove(index);
+						<QueuedPlayers<T>>::remove(&red.player);
+						<QueuedPlayers<T>>::remove(&blue.player);
+						if let Err(err) = Self::open_board(red.player.clone(), blue.player.clone(), settings) {
+							info!("connectfour: dropped matched pair: {:?}", err);
+							Self::deposit_event(Event::Dequeued(red.player));
+							Self::deposit_event(Event::Dequeued(blue.player));
+						}
+						matches += 1;
+						other = 0;
+						weight = weight.saturating_add(T::DbWeight::get().reads_writes(8, 14));
+					},
+					// Nobody left to compare with, move on to the next waiting player.
+					None if other >= queue.len() => {
+						index += 1;
+						other = 0;
+					},
+					None => {},
+				}
+			}
+
+			// Stay in a queue the budgets ran out in.
+			let scanned = index >= queue.len();
+			if queue.len() != waiting {
+				if queue.is_empty() {
+					<MatchQueues<T>>::remove(settings);
+				} else {
+					<MatchQueues<T>>::insert(settings, queue);
+				}
+				weight = weight.saturating_add(T::DbWeight::get().writes(1));
+			}
+			if !scanned {
+				break
+			}
+			queue_index = (queue_index + 1) % all_settings.len();
+			index = 0;
+			other = 0;
+		}
+
+		<QueueCursor<T>>::put((queue_index as u32, index as u32, other as u32));
+
+		weight
+	}
+
+	/// Remove the open challenge of a creator from the lobby, returns the removed challenge.
+	fn withdraw_challenge(
+		creator: &T::AccountId
+	) -> Option<ChallengeOf<T>> {
+		let mut lobby = Self::lobby();
+		let position = lobby.iter().position(|challenge| challenge.creator == *creator)?;
+		let challenge = lobby.remove(position);
+		<Lobby<T>>::put(lobby);
+		Some(challenge)
+	}
+
+	/// Remove the open challenges that expired without being accepted.
+	fn process_lobby(
+		now: T::BlockNumber
+	) -> Weight {
+		let mut lobby = Self::lobby();
+		let open = lobby.len();
+
+		lobby.retain(|challenge| {
+			if challenge.expires > now {
+				return true
+			}
+			Self::deposit_event(Event::ChallengeExpired(challenge.creator.clone()));
+			false
+		});
+
+		if lobby.len() == open {
+			return T::DbWeight::get().reads(1);
+		}
+		<Lobby<T>>::put(lobby);
+		T::DbWeight::get().reads_writes(1, 1)
 	}
-}
 
-impl<T: Config> Pallet<T> {
+	/// Count a timeout caused by a player, reaching the threshold of recent timeouts bans them
+	/// from starting boards for twice as long as their previous ban.
+	fn record_timeout(
+		who: &T::AccountId
+	) {
+		let now = <frame_system::Pallet<T>>::block_number();
+
+		let banned_until = <PenaltyBox<T>>::mutate(who, |penalty| {
+			if now.saturating_sub(penalty.last_timeout) > T::TimeoutWindow::get() {
+				penalty.timeouts = 0;
+			}
+			penalty.timeouts = penalty.timeouts.saturating_add(1);
+			penalty.last_timeout = now;
 
-	/// Update nonce once used. 
-	fn encode_and_update_nonce(
-	) -> Vec<u8> {
-		let nonce = <Nonce<T>>::get();
-		<Nonce<T>>::put(nonce.wrapping_add(1));
-		nonce.encode()
+			if penalty.timeouts < T::TimeoutThreshold::get() {
+				return None
+			}
+			let length = T::BanLength::get().saturating_mul(2u32.saturating_pow(penalty.bans).into());
+			penalty.timeouts = 0;
+			penalty.bans = penalty.bans.saturating_add(1);
+			penalty.banned_until = now.saturating_add(length);
+			Some(penalty.banned_until)
+		});
+
+		if let Some(until) = banned_until {
+			// Banned players stop waiting for boards.
+			if Self::leave_queue(who) {
+				Self::deposit_event(Event::Dequeued(who.clone()));
+			}
+			if Self::withdraw_challenge(who).is_some() {
+				Self::deposit_event(Event::ChallengeCancelled(who.clone()));
+			}
+			Self::deposit_event(Event::PlayerBanned(who.clone(), until));
+		}
 	}
 
-	/// Generates a random hash out of a seed.
-	fn generate_random_hash(
-		phrase: &[u8], 
-		sender: T::AccountId
-	) -> T::Hash {
-		let (seed, _) = T::Randomness::random(phrase);
-		let seed = <[u8; 32]>::decode(&mut TrailingZeroInput::new(seed.as_ref()))
-			.expect("input is padded with zeroes; qed");
-		return (seed, &sender, Self::encode_and_update_nonce()).using_encoded(T::Hashing::hash);
+	/// Remove an account from the friends list of a player, returns if it was on the list.
+	fn unfriend(
+		who: &T::AccountId,
+		friend: &T::AccountId
+	) -> bool {
+		let mut friends = Self::friends(who);
+		match friends.iter().position(|account| account == friend) {
+			Some(position) => {
+				friends.remove(position);
+				if friends.is_empty() {
+					<Friends<T>>::remove(who);
+				} else {
+					<Friends<T>>::insert(who, friends);
+				}
+				true
+			},
+			None => false,
+		}
 	}
 
-	/// Generate a new game between two players.
-	fn create_game(
-		red: T::AccountId, 
-		blue: T::AccountId
-	) -> T::Hash {
-		// get a random hash as board id
-		let board_id = Self::generate_random_hash(b"create", red.clone());
-		// calculate plyer to start the first turn, with the first byte of the board_id random hash
-		let next_player = if board_id.as_ref()[0] < 128 { PLAYER_1 } else { PLAYER_2 };
+	/// Make sure a player isn't banned from starting boards.
+	fn ensure_not_banned(
+		who: &T::AccountId
+	) -> DispatchResult {
+		let now = <frame_system::Pallet<T>>::block_number();
+		ensure!(Self::penalty_box(who).banned_until <= now, Error::<T>::PlayerBanned);
+		Ok(())
+	}
+
+	/// Make sure a stake can be wagered in the asset, native stakes have no minimum.
+	fn ensure_stake(
+		asset: Option<T::AssetId>,
+		stake: BalanceOf<T>
+	) -> DispatchResult {
+		if let Some(asset) = asset {
+			let min_stake = Self::min_stakes(asset).ok_or(Error::<T>::AssetNotAllowed)?;
+			ensure!(!stake.is_zero() && stake >= min_stake, Error::<T>::StakeTooSmall);
+		}
+		Ok(())
+	}
+
+	/// The number of blocks a turn of a board lasts.
+	fn turn_length(
+		board_id: T::Hash
+	) -> T::BlockNumber {
+		Self::board_settings(board_id).turn_blocks.into()
+	}
+
+	/// Remove a board without a result, the deposits are returned to both players.
+	fn void_board(
+		board_id: T::Hash,
+		board: BoardOf<T>
+	) {
+		T::Currency::unreserve(&board.red, <BoardDeposits<T>>::take(board_id, &board.red));
+		T::Currency::unreserve(&board.blue, <BoardDeposits<T>>::take(board_id, &board.blue));
+		Self::remove_board(board_id, board);
+
+		Self::deposit_event(Event::GameVoided(board_id));
+	}
+
+	/// Release the board deposits, a player that let the board time out without placing a
+	/// stone loses it.
+	fn release_deposits(
+		board_id: T::Hash,
+		board: &BoardOf<T>
+	) {
+		for (player, account) in [(PLAYER_1, &board.red), (PLAYER_2, &board.blue)].iter() {
+			let deposit = <BoardDeposits<T>>::take(board_id, *account);
+			let stalled = <StalledPlayers<T>>::take(board_id, *account);
+			if stalled && Logic::stones(board.board, *player) == 0 {
+				let (imbalance, _) = T::Currency::slash_reserved(account, deposit);
+				Self::deposit_event(Event::DepositSlashed(board_id, (*account).clone(), imbalance.peek()));
+			} else {
+				T::Currency::unreserve(account, deposit);
+			}
+		}
+	}
+
+	/// Queue a board to be handled once the deadline block is reached, replacing its previous
+	/// deadline. The previous queue entry stays behind and gets skipped once it expires.
+	fn schedule_deadline(
+		board_id: T::Hash,
+		deadline: T::BlockNumber
+	) {
+		<Deadlines<T>>::append(deadline, board_id);
+		<BoardSchedules<T>>::insert(board_id, deadline);
+	}
+
+	/// Handle the boards whose deadline expired, within the per block budget.
+	fn process_deadlines(
+		now: T::BlockNumber
+	) -> Weight {
+		let mut weight = T::DbWeight::get().reads_writes(1, 1);
+
+		let mut expired = <Deadlines<T>>::take(now);
+		if expired.is_empty() {
+			return weight;
+		}
+
+		let max_deadlines = T::MaxDeadlinesPerBlock::get();
+		let mut used: u32 = 0;
+		let mut handled = 0;
+		for board_id in expired.iter() {
+			weight = weight.saturating_add(T::DbWeight::get().reads(1));
+
+			// Skip entries of deadlines that got replaced by a later one.
+			let due = matches!(Self::board_schedules(board_id), Some(deadline) if deadline <= now);
+			let bettors = if due {
+				weight = weight.saturating_add(T::DbWeight::get().reads(1));
+				Self::market_bettors(board_id)
+			} else {
+				0
+			};
+
+			// Stop once the budget is used up, the first board is always handled.
+			let cost = bettors.saturating_add(1);
+			if used > 0 && used.saturating_add(cost) > max_deadlines {
+				break
+			}
+			used = used.saturating_add(cost);
+			handled += 1;
+
+			if !due {
+				continue
+			}
+			if Self::end_turn(*board_id).is_err() {
+				frame_support::print("LOGIC ERROR: process_deadlines/end_turn failed");
+			}
+			weight = weight.saturating_add(Self::finish_weight(bettors));
+		}
+
+		// Carry over the deadlines exceeding the budget to the next block.
+		if handled < expired.len() {
+			let mut carried = expired.split_off(handled);
+			<Deadlines<T>>::mutate(now + One::one(), |next| {
+				carried.append(next);
+				*next = carried;
+			});
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+		}
+
+		weight
+	}
+
+	/// End the turn of a board whose deadline expired. A running board is won by the player
+	/// waiting for the turn and the staller gets slashed, unless the opponent never committed to
+	/// it and it gets voided. A finished board gets cleaned up.
+	fn end_turn(
+		board_id: T::Hash
+	) -> DispatchResult {
+		ensure!(Boards::<T>::contains_key(&board_id), "No board found.");
+		let board = Self::boards(&board_id);
+
+		if board.board_state == BoardState::Running {
+
+			// A board the opponent never committed to has no result.
+			if Self::coin_tosses(&board_id).map_or(false, |toss| toss.blue_commit.is_none()) {
+				Self::void_board(board_id, board);
+				return Ok(())
+			}
+
+			let winner = Self::timeout_winner(board_id, &board)?;
+
+			// Only players who committed to the board get penalised for stalling it.
+			Self::slash_timeout(board_id, &board, &winner);
+			let committed = Self::coin_tosses(&board_id).map_or(true, |toss| toss.committed());
+			for player in [&board.red, &board.blue].iter() {
+				if **player != winner {
+					<StalledPlayers<T>>::insert(board_id, *player, true);
+					if committed {
+						Self::record_timeout(player);
+					}
+				}
+			}
+
+			Self::finish_board(board_id, board, BoardState::Finished(winner), EndReason::Timeout);
+
+		} else {
+
+			// do cleanup after final force turn.
+			Self::release_deposits(board_id, &board);
+			Self::remove_board(board_id, board);
+		}
+
+		Ok(())
+	}
+
+	/// Slash part of the deposit of the players who let a running board time out, as configured
+	/// for the game type. The opponent receives their share unless they stalled as well, the
+	/// rest goes to the founder key.
+	fn slash_timeout(
+		board_id: T::Hash,
+		board: &BoardOf<T>,
+		winner: &T::AccountId
+	) {
+		let slash = if Wagers::<T>::contains_key(&board_id) {
+			T::WageredTimeoutSlash::get()
+		} else {
+			T::CasualTimeoutSlash::get()
+		};
+		if slash.is_zero() {
+			return
+		}
+
+		for (staller, opponent) in [(&board.red, &board.blue), (&board.blue, &board.red)].iter() {
+			if *staller == winner {
+				continue
+			}
+			let deposit = Self::board_deposits(board_id, *staller);
+			let amount = slash.mul_floor(deposit);
+			if amount.is_zero() {
+				continue
+			}
+
+			let reward = if *opponent == winner {
+				T::TimeoutSlashReward::get().mul_floor(amount)
+			} else {
+				Zero::zero()
+			};
+			if !reward.is_zero() &&
+				T::Currency::repatriate_reserved(staller, opponent, reward, BalanceStatus::Free).is_err() {
+				frame_support::print("LOGIC ERROR: slash_timeout/repatriate_reserved failed");
+			}
+
+			// Burn the rest if there is no founder key able to receive it.
+			let rest = amount.saturating_sub(reward);
+			let paid = Self::founder_key().map_or(false, |founder|
+				T::Currency::repatriate_reserved(staller, &founder, rest, BalanceStatus::Free).is_ok()
+			);
+			if !paid {
+				T::Currency::slash_reserved(staller, rest);
+			}
+
+			<BoardDeposits<T>>::insert(board_id, *staller, deposit.saturating_sub(amount));
+			Self::deposit_event(Event::TimeoutSlashed(board_id, (*staller).clone(), amount));
+		}
+	}
+
+	/// The sponsor paying the fee of a call, the player's sponsor comes before the board's.
+	/// Only `new_game` and `play_turn` calls get sponsored, by a live budget covering the fee.
+	pub fn fee_sponsor(
+		who: &T::AccountId,
+		call: &<T as frame_system::Config>::Call,
+		fee: BalanceOf<T>
+	) -> Option<T::AccountId> where
+		<T as frame_system::Config>::Call: IsSubType<Call<T>>,
+	{
+		let now = <frame_system::Pallet<T>>::block_number();
+		let covers = |sponsor: &T::AccountId| Self::sponsorships(sponsor)
+			.map_or(false, |sponsorship| now <= sponsorship.expires && sponsorship.budget >= fee);
+
+		let player = match call.is_sub_type() {
+			Some(Call::new_game(..)) => who.clone(),
+			Some(Call::play_turn(..)) => Self::move_player(who),
+			_ => return None,
+		};
+		if let Some(sponsor) = Self::sponsored_players(&player).filter(|sponsor| covers(sponsor)) {
+			return Some(sponsor)
+		}
+		if let Some(Call::play_turn(..)) = call.is_sub_type() {
+			if PlayerBoard::<T>::contains_key(&player) {
+				return Self::sponsored_boards(Self::player_board(&player)).filter(|sponsor| covers(sponsor))
+			}
+		}
+
+		None
+	}
+
+	/// The player a move is placed for, either the sender or the owner of a valid session key.
+	fn move_player(
+		sender: &T::AccountId
+	) -> T::AccountId {
+		if !PlayerBoard::<T>::contains_key(sender) {
+			if let Some(move_key) = Self::move_keys(sender) {
+				let now = <frame_system::Pallet<T>>::block_number();
+				let expired = move_key.expires.map_or(false, |expires| now > expires);
+				if !expired && Self::player_board(&move_key.owner) == move_key.board_id {
+					return move_key.owner
+				}
+			}
+		}
+
+		sender.clone()
+	}
+
+	/// Check that the sender can place a stone in the column of their board, returns the board.
+	fn validate_move(
+		sender: &T::AccountId,
+		column: u8
+	) -> Result<(T::Hash, BoardOf<T>), DispatchError> {
+		ensure!(PlayerBoard::<T>::contains_key(sender), Error::<T>::NoPlayerBoard);
+		let board_id = Self::player_board(sender);
+
+		// Get board from player.
+		ensure!(Boards::<T>::contains_key(&board_id), "No board found");
+		let board = Self::boards(&board_id);
+
+		// Board is still open to play and not finished.
+		ensure!(board.board_state == BoardState::Running, "Board is not running, check if already finished.");
+
+		// The coin toss has to decide the starting player first.
+		ensure!(!CoinTosses::<T>::contains_key(&board_id), Error::<T>::TossPending);
+
+		// Check if correct player is at turn
+		let current_account = if board.next_player == PLAYER_1 {
+			&board.red
+		} else if board.next_player == PLAYER_2 {
+			&board.blue
+		} else {
+			return Err(Error::<T>::WrongLogic.into())
+		};
+		ensure!(sender == current_account, Error::<T>::NotPlayerTurn);
+
+		ensure!(Logic::can_add_stone(board.board, column), Error::<T>::InvalidColumn);
+
+		Ok((board_id, board))
+	}
+
+	/// The player winning a board when its deadline expires. During the coin toss the player
+	/// who stalled it forfeits, if both stalled there is no winner.
+	fn timeout_winner(
+		board_id: T::Hash,
+		board: &BoardOf<T>
+	) -> Result<T::AccountId, DispatchError> {
+		if let Some(toss) = Self::coin_tosses(&board_id) {
+			return Ok(match toss.done() {
+				(true, false) => board.red.clone(),
+				(false, true) => board.blue.clone(),
+				_ => Default::default(),
+			})
+		}
+
+		if board.next_player == PLAYER_1 {
+			Ok(board.blue.clone())
+		} else if board.next_player == PLAYER_2 {
+			Ok(board.red.clone())
+		} else {
+			Err(Error::<T>::WrongLogic.into())
+		}
+	}
+
+	/// Finish a board with the given state and schedule its cleanup.
+	fn finish_board(
+		board_id: T::Hash,
+		mut board: BoardOf<T>,
+		board_state: BoardState<T::AccountId>,
+		reason: EndReason
+	) {
 		// get current blocknumber
-		let block_number = <frame_system::Pallet<T>>::block_number();
-		// create a new empty bgame oard
-		let board = BoardStruct {
-			id: board_id,
-			red: red,
-			blue: blue,
-			board: [[0u8; 6]; 7],
-			last_turn: block_number,
-			next_player: next_player,
-			board_state: BoardState::Running,
+		let last_turn = <frame_system::Pallet<T>>::block_number();
+		board.last_turn = last_turn;
+		board.board_state = board_state;
+
+		// A board finished during the coin toss can't be tossed anymore.
+		<CoinTosses<T>>::remove(board_id);
+
+		// Pay out the stakes of a wagered board and the bets of spectators
+		Self::settle_wager(board_id, &board);
+		Self::settle_market(board_id, &board);
+		Self::pay_rewards(board_id, &board);
+
+		let (red_change, blue_change) = match Self::board_settings(board_id).mode {
+			GameMode::Ranked => Self::update_ratings(&board),
+			GameMode::Casual => (0, 0),
 		};
-		// insert the new board into the storage
+		Self::record_stats(&board, reason);
+		let winner = match &board.board_state {
+			BoardState::Finished(winner) if *winner != T::AccountId::default() => Some(winner.clone()),
+			_ => None,
+		};
+		Self::deposit_event(Event::GameFinished(board_id, winner, red_change, blue_change));
+
+		// Write final board state back into the storage
 		<Boards<T>>::insert(board_id, board);
-		// emit event for a new board creation
-		// Emit an event.
-		Self::deposit_event(Event::NewBoard(board_id));
 
-		return board_id;
+		// Schedule cleanup task
+		Self::schedule_deadline(board_id, last_turn + CLEANUP_BOARDS_AFTER.into());
+	}
+
+	/// Upper bound of the weight of finishing a board, the stakes, ratings, stats and rewards
+	/// of both players get settled and each bettor of its market gets paid out or refunded.
+	pub fn finish_weight(
+		bettors: u32
+	) -> Weight {
+		T::DbWeight::get().reads_writes(24, 24)
+			.saturating_add(T::DbWeight::get().reads_writes(2, 3).saturating_mul(bettors as Weight))
+	}
+
+	/// Number of spectators betting on a board.
+	fn market_bettors(
+		board_id: &T::Hash
+	) -> u32 {
+		Self::markets(board_id).map_or(0, |market| market.bettors)
+	}
+
+	/// The account holding the asset stakes of running boards.
+	pub fn escrow_account() -> T::AccountId {
+		T::PalletId::get().into_account()
+	}
+
+	/// Update the Elo ratings of the players of a finished board, players with less than the
+	/// provisional number of games move faster. Returns the rating changes of red and blue.
+	fn update_ratings(
+		board: &BoardOf<T>
+	) -> (i32, i32) {
+		let red_score = match &board.board_state {
+			BoardState::Finished(winner) if *winner == board.red => elo::WIN,
+			BoardState::Finished(winner) if *winner == board.blue => elo::LOSS,
+			_ => elo::DRAW,
+		};
+		let mut red = Self::season_rating(&board.red);
+		let mut blue = Self::season_rating(&board.blue);
+
+		let k_factor = |rating: &Rating| if rating.games < PROVISIONAL_GAMES { PROVISIONAL_K_FACTOR } else { K_FACTOR };
+		let red_change = Elo::change(red.rating, blue.rating, red_score, k_factor(&red));
+		let blue_change = Elo::change(blue.rating, red.rating, elo::WIN - red_score, k_factor(&blue));
+
+		red.rating = Elo::apply(red.rating, red_change);
+		red.games = red.games.saturating_add(1);
+		blue.rating = Elo::apply(blue.rating, blue_change);
+		blue.games = blue.games.saturating_add(1);
+		Self::update_leaderboard(&board.red, red.rating);
+		Self::update_leaderboard(&board.blue, blue.rating);
+		<Ratings<T>>::insert(&board.red, red);
+		<Ratings<T>>::insert(&board.blue, blue);
+
+		(red_change, blue_change)
+	}
+
+	/// The rating of a player in the current season. Ratings of past seasons are moved towards
+	/// the initial rating once for every season passed.
+	pub fn season_rating(
+		who: &T::AccountId
+	) -> Rating {
+		let mut rating = Self::ratings(who);
+		let season = Self::current_season();
+		let factor = T::SeasonResetFactor::get();
+
+		// Stop once the rating doesn't move anymore.
+		while rating.season < season && rating.rating != INITIAL_RATING && factor != Perbill::one() {
+			rating.rating = if rating.rating > INITIAL_RATING {
+				INITIAL_RATING + factor.mul_floor(rating.rating - INITIAL_RATING)
+			} else {
+				INITIAL_RATING - factor.mul_floor(INITIAL_RATING - rating.rating)
+			};
+			rating.season += 1;
+		}
+		rating.season = season;
+
+		rating
+	}
+
+	/// Move a player to their place on the leaderboard. Players reaching a rating go below the
+	/// players already there with the same rating, the last place drops out of a full board.
+	fn update_leaderboard(
+		who: &T::AccountId,
+		rating: u32
+	) {
+		let mut entries = Self::leaderboard().to_vec();
+		entries.retain(|(account, _)| account != who);
+
+		let index = entries.iter().position(|(_, other)| rating > *other).unwrap_or(entries.len());
+		entries.insert(index, (who.clone(), rating));
+		entries.truncate(T::LeaderboardSize::get() as usize);
+
+		match BoundedVec::try_from(entries) {
+			Ok(entries) => <Leaderboard<T>>::put(entries),
+			Err(_) => frame_support::print("LOGIC ERROR: update_leaderboard/try_from failed"),
+		}
+	}
+
+	/// Archive the leaderboard and start the next season once the current season passed.
+	fn rollover_season(
+		now: T::BlockNumber
+	) -> Weight {
+		let season = (now / T::SeasonLength::get().max(One::one())).saturated_into::<u32>();
+		let current_season = Self::current_season();
+		if season <= current_season {
+			return T::DbWeight::get().reads(1)
+		}
+
+		<SeasonStandings<T>>::insert(current_season, <Leaderboard<T>>::take());
+		<CurrentSeason<T>>::put(season);
+
+		Self::deposit_event(Event::SeasonEnded(current_season));
+
+		T::DbWeight::get().reads_writes(2, 3)
+	}
+
+	/// Average number of stones on the finished boards of a player.
+	pub fn average_moves(
+		who: &T::AccountId
+	) -> u32 {
+		let stats = Self::player_stats(who);
+		stats.moves / stats.games.max(1)
+	}
+
+	/// The player who placed the first stone on a board, if the coin toss decided it.
+	fn first_player(
+		board: &BoardOf<T>
+	) -> Option<&T::AccountId> {
+		let red_stones = Logic::stones(board.board, PLAYER_1);
+		let blue_stones = Logic::stones(board.board, PLAYER_2);
+
+		// With the same number of stones the starting player is at turn.
+		let first = if red_stones > blue_stones {
+			PLAYER_1
+		} else if blue_stones > red_stones {
+			PLAYER_2
+		} else {
+			board.next_player
+		};
+
+		match first {
+			PLAYER_1 => Some(&board.red),
+			PLAYER_2 => Some(&board.blue),
+			_ => None,
+		}
+	}
+
+	/// Record the result of a finished board in the statistics of both players.
+	fn record_stats(
+		board: &BoardOf<T>,
+		reason: EndReason
+	) {
+		let winner = match &board.board_state {
+			BoardState::Finished(winner) => winner,
+			_ => return,
+		};
+		let moves = Logic::moves(board.board) as u32;
+		let first = Self::first_player(board);
+
+		for (player, opponent) in [(&board.red, &board.blue), (&board.blue, &board.red)].iter() {
+			<PlayerStats<T>>::mutate(*player, |stats| {
+				stats.games = stats.games.saturating_add(1);
+				stats.moves = stats.moves.saturating_add(moves);
+				if first == Some(*player) {
+					stats.first_player_games = stats.first_player_games.saturating_add(1);
+				}
+
+				if *player == winner {
+					stats.wins = stats.wins.saturating_add(1);
+					stats.streak = stats.streak.saturating_add(1);
+					stats.best_streak = stats.best_streak.max(stats.streak);
+				} else {
+					stats.streak = 0;
+					if *opponent == winner {
+						stats.losses = stats.losses.saturating_add(1);
+					} else {
+						stats.draws = stats.draws.saturating_add(1);
+					}
+				}
+
+				match reason {
+					EndReason::Timeout if *player == winner => {
+						stats.timeouts_suffered = stats.timeouts_suffered.saturating_add(1);
+					},
+					EndReason::Timeout => {
+						stats.timeouts_caused = stats.timeouts_caused.saturating_add(1);
+					},
+					EndReason::Resigned if *opponent == winner => {
+						stats.resignations = stats.resignations.saturating_add(1);
+					},
+					_ => {},
+				}
+			});
+		}
+	}
+
+	/// Take the stake of a wagered board from a player committing to play it, native stakes get
+	/// reserved next to the given deposit and asset stakes moved into the escrow account.
+	fn place_stake(
+		board_id: T::Hash,
+		who: &T::AccountId,
+		deposit: BalanceOf<T>
+	) -> DispatchResult {
+		if let Some(wager) = Self::wagers(&board_id) {
+			let stake = wager.stake;
+			match wager.asset {
+				Some(asset) => {
+					T::Assets::transfer(asset, who, &Self::escrow_account(), stake, false)
+						.map_err(|_| Error::<T>::InsufficientStake)?;
+				},
+				None => {
+					ensure!(T::Currency::can_reserve(who, stake.saturating_add(deposit)), Error::<T>::InsufficientStake);
+					T::Currency::reserve(who, stake)?;
+				},
+			}
+			<BoardStakes<T>>::insert(board_id, who, stake);
+
+			Self::deposit_event(Event::StakePlaced(board_id, who.clone(), stake));
+		}
+
+		Ok(())
+	}
+
+	/// Return the stakes still held for a wagered board, asset stakes get returned from the
+	/// escrow account.
+	fn return_stakes(
+		board_id: T::Hash,
+		wager: &WagerOf<T>
+	) {
+		for (player, stake) in <BoardStakes<T>>::drain_prefix(board_id) {
+			match wager.asset {
+				Some(asset) => {
+					if T::Assets::transfer(asset, &Self::escrow_account(), &player, stake, false).is_err() {
+						frame_support::print("LOGIC ERROR: return_stakes/transfer failed");
+					}
+				},
+				None => {
+					T::Currency::unreserve(&player, stake);
+				},
+			}
+		}
+	}
+
+	/// Pay an amount out of the stake a player put up on a wager, returns the amount paid.
+	fn pay_stake(
+		wager: &WagerOf<T>,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: BalanceOf<T>
+	) -> Result<BalanceOf<T>, DispatchError> {
+		match wager.asset {
+			Some(asset) => T::Assets::transfer(asset, &Self::escrow_account(), to, amount, false),
+			None => T::Currency::repatriate_reserved(from, to, amount, BalanceStatus::Free)
+				.map(|missing| amount.saturating_sub(missing)),
+		}
+	}
+
+	/// Settle a finished wagered board, the winner receives the stake the opponent put up minus
+	/// the rake on the pot paid to the founder key, and a draw returns the stakes.
+	fn settle_wager(
+		board_id: T::Hash,
+		board: &BoardOf<T>
+	) {
+		let wager = match <Wagers<T>>::take(board_id) {
+			Some(wager) => wager,
+			None => return,
+		};
+
+		let loser = match &board.board_state {
+			BoardState::Finished(winner) if *winner == board.red => Some((winner, &board.blue)),
+			BoardState::Finished(winner) if *winner == board.blue => Some((winner, &board.red)),
+			_ => None,
+		};
+		if let Some((winner, loser)) = loser {
+			let stake = <BoardStakes<T>>::take(board_id, loser);
+			if !stake.is_zero() {
+				// The rake is taken from the whole pot, but never more than the stake of the loser.
+				let mut prize = stake;
+				if let Some(founder) = Self::founder_key() {
+					let rake = T::Rake::get().mul_floor(wager.stake.saturating_add(wager.stake)).min(stake);
+					if !rake.is_zero() {
+						// The winner keeps the rake if the founder key can't receive it.
+						if let Ok(paid) = Self::pay_stake(&wager, loser, &founder, rake) {
+							prize = prize.saturating_sub(paid);
+							Self::deposit_event(Event::RakePaid(board_id, founder, paid));
+						}
+					}
+				}
+
+				match Self::pay_stake(&wager, loser, winner, prize) {
+					Ok(paid) => Self::deposit_event(Event::WagerPaid(board_id, winner.clone(), paid)),
+					Err(_) => frame_support::print("LOGIC ERROR: settle_wager/pay_stake failed"),
+				}
+			}
+		}
+
+		// The stake of the winner, or both stakes of a draw, get returned.
+		Self::return_stakes(board_id, &wager);
+	}
+
+	/// Settle the market of a finished board. The backers of the winner get their bets back
+	/// together with their share of the pool of the loser, a draw or a winner nobody backed
+	/// refunds all bets.
+	fn settle_market(
+		board_id: T::Hash,
+		board: &BoardOf<T>
+	) {
+		let market = match <Markets<T>>::take(board_id) {
+			Some(market) if market.bettors > 0 => market,
+			_ => return,
+		};
+
+		let (winning_pool, losing_pool, red_won) = match &board.board_state {
+			BoardState::Finished(winner) if *winner == board.red => (market.red_pool, market.blue_pool, true),
+			BoardState::Finished(winner) if *winner == board.blue => (market.blue_pool, market.red_pool, false),
+			_ => (Zero::zero(), Zero::zero(), false),
+		};
+		if winning_pool.is_zero() {
+			Self::refund_bets(board_id);
+			return
+		}
+
+		// Collect the bets on the loser into the pot first.
+		let bets: Vec<_> = <Bets<T>>::drain_prefix(board_id).collect();
+		let mut pot = <T::Currency as Currency<T::AccountId>>::NegativeImbalance::zero();
+		for (bettor, bet) in bets.iter() {
+			let lost = if red_won { bet.blue } else { bet.red };
+			if !lost.is_zero() {
+				pot.subsume(T::Currency::slash_reserved(bettor, lost).0);
+			}
+		}
+
+		// Pay out the pot in proportion to the bets on the winner.
+		for (bettor, bet) in bets.iter() {
+			let won = if red_won { bet.red } else { bet.blue };
+			if won.is_zero() {
+				continue
+			}
+			T::Currency::unreserve(bettor, won);
+
+			let share = multiply_by_rational(
+				won.saturated_into::<u128>(),
+				losing_pool.saturated_into::<u128>(),
+				winning_pool.saturated_into::<u128>(),
+			).map(|share| share.saturated_into::<BalanceOf<T>>()).unwrap_or_else(|_| Zero::zero());
+			let (payout, rest) = pot.split(share);
+			pot = rest;
+			let paid = won.saturating_add(payout.peek());
+			T::Currency::resolve_creating(bettor, payout);
+
+			Self::deposit_event(Event::BetPaid(board_id, bettor.clone(), paid));
+		}
+
+		// Rounding leftovers go to the founder key.
+		if let Some(founder) = Self::founder_key() {
+			T::Currency::resolve_creating(&founder, pot);
+		}
 	}
 
-	/// Schedule end turn
-	fn schedule_end_turn(
-		board_id: T::Hash, 
-		last_turn: T::BlockNumber, 
-		end_turn: T::BlockNumber
-	) -> Option<Vec<u8>> {
+	/// Return the reserved bets of all spectators of a board.
+	fn refund_bets(
+		board_id: T::Hash
+	) {
+		for (bettor, bet) in <Bets<T>>::drain_prefix(board_id) {
+			T::Currency::unreserve(&bettor, bet.red.saturating_add(bet.blue));
+		}
+		Self::deposit_event(Event::BetsRefunded(board_id));
+	}
+
+	/// Mint the rewards of a finished board. Boards need the minimum number of stones, players
+	/// aren't rewarded against their recent opponents again and the rewards of an era are capped.
+	fn pay_rewards(
+		board_id: T::Hash,
+		board: &BoardOf<T>
+	) {
+		let winner = match &board.board_state {
+			BoardState::Finished(winner) => winner,
+			_ => return,
+		};
+		if Logic::moves(board.board) < T::MinRewardMoves::get() {
+			return
+		}
+
+		let now = <frame_system::Pallet<T>>::block_number();
+		let era = now / T::RewardEraLength::get().max(One::one());
+		let draw = *winner == T::AccountId::default();
+
+		for (player, opponent) in [(&board.red, &board.blue), (&board.blue, &board.red)].iter() {
+			let reward = if *player == winner {
+				T::WinReward::get()
+			} else if draw {
+				T::DrawReward::get()
+			} else {
+				continue
+			};
+
+			let mut recent = Self::recent_opponents(*player);
+			if reward.is_zero() || recent.contains(*opponent) {
+				continue
+			}
+
+			// Start a new era once the current one passed.
+			let (current_era, mut emitted) = Self::era_emission();
+			if current_era != era {
+				emitted = Zero::zero();
+			}
+			let amount = reward.min(T::MaxEraEmission::get().saturating_sub(emitted));
+			if amount.is_zero() {
+				continue
+			}
+
+			let paid = T::Currency::deposit_creating(player, amount).peek();
+			if paid.is_zero() {
+				continue
+			}
+			<EraEmission<T>>::put((era, emitted.saturating_add(paid)));
+
+			recent.push((*opponent).clone());
+			if recent.len() > T::RewardOpponentWindow::get() as usize {
+				recent.remove(0);
+			}
+			<RecentOpponents<T>>::insert(*player, recent);
+
+			Self::deposit_event(Event::RewardPaid(board_id, (*player).clone(), paid));
+		}
+	}
 
-		//ensure!(end_turn > <frame_system::Pallet<T>>::block_number(), "Can't schedule a end turn in the past.");
-		let schedule_task_id = (CONNECTFOUR_ID, board_id, last_turn).encode();
+	/// Remove a board together with the player entries pointing at it.
+	fn remove_board(
+		board_id: T::Hash,
+		board: BoardOf<T>
+	) {
+		for player in [&board.red, &board.blue].iter() {
+			if let Some(key) = <PlayerMoveKey<T>>::take(*player) {
+				<MoveKeys<T>>::remove(key);
+			}
+		}
+		<Boards<T>>::remove(board_id);
+		<PlayerBoard<T>>::remove(board.red);
+		<PlayerBoard<T>>::remove(board.blue);
+		Self::clear_board(board_id);
+	}
 
-		if T::Scheduler::schedule_named(
-			schedule_task_id.clone(),
-			DispatchTime::At(end_turn),
-			None,
-			63,
-			frame_system::RawOrigin::Root.into(),
-			Call::force_end_turn(board_id, last_turn).into(),
-		).is_err() {
-			frame_support::print("LOGIC ERROR: test_schedule/schedule_named failed");
-			return None
+	/// Remove the storage kept next to a board, boards removed without a result return the
+	/// stakes and bets. Returns whether anything was left to remove.
+	fn clear_board(
+		board_id: T::Hash
+	) -> bool {
+		let mut cleared = false;
+		if let Some(wager) = <Wagers<T>>::take(board_id) {
+			Self::return_stakes(board_id, &wager);
+			cleared = true;
+		}
+		if let Some(market) = <Markets<T>>::take(board_id) {
+			if market.bettors > 0 {
+				Self::refund_bets(board_id);
+			}
+			cleared = true;
+		}
+		if let Some(game_number) = <BoardNumbers<T>>::take(board_id) {
+			<GameNumbers<T>>::remove(game_number);
+			cleared = true;
 		}
+		cleared |= <SponsoredBoards<T>>::take(board_id).is_some();
+		cleared |= <BoardSchedules<T>>::take(board_id).is_some();
+		cleared |= <CoinTosses<T>>::take(board_id).is_some();
+		cleared |= BoardSettings::<T>::contains_key(board_id);
+		<BoardSettings<T>>::remove(board_id);
+		<StalledPlayers<T>>::remove_prefix(board_id);
+		cleared
+	}
+}
+
+/// Validate `play_turn` calls before they enter the transaction pool, so moves out of turn or
+/// into an illegal column are never included and never pay fees. Valid moves provide a tag of
+/// their board and move number, only one move per turn can be included.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckMove<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckMove<T> {
+	/// Create new `SignedExtension` to check connect four moves.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckMove<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> sp_std::fmt::Debug for CheckMove<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckMove")
+	}
 
-		Some(schedule_task_id)
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
 	}
+}
 
+impl<T: Config + Send + Sync> SignedExtension for CheckMove<T> where
+	<T as frame_system::Config>::Call: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckMove";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		if let Some(Call::play_turn(column)) = call.is_sub_type() {
+			let player = Pallet::<T>::move_player(who);
+			let (board_id, board) = Pallet::<T>::validate_move(&player, *column)
+				.map_err(|_| InvalidTransaction::Call)?;
+
+			return ValidTransaction::with_tag_prefix("ConnectFour")
+				.and_provides((board_id, Logic::moves(board.board)))
+				.longevity(MAX_BLOCKS_PER_TURN.into())
+				.build()
+		}
 
+		Ok(ValidTransaction::default())
+	}
 }
 
+/// Charge the fees of sponsored `new_game` and `play_turn` calls to the budget of the sponsor,
+/// all other fees are charged to the sender. Wraps the adapter withdrawing the fees, such as
+/// `CurrencyAdapter`, which has to use the same currency as the pallet.
+pub struct SponsoredFees<T, OCT>(PhantomData<(T, OCT)>);
+
+impl<T, OCT> OnChargeTransaction<T> for SponsoredFees<T, OCT> where
+	T: Config + pallet_transaction_payment::Config,
+	<T as frame_system::Config>::Call: IsSubType<Call<T>>,
+	OCT: OnChargeTransaction<T, Balance = BalanceOf<T>>,
+{
+	type Balance = BalanceOf<T>;
+	type LiquidityInfo = (Option<(T::AccountId, BalanceOf<T>)>, OCT::LiquidityInfo);
+
+	fn withdraw_fee(
+		who: &T::AccountId,
+		call: &<T as frame_system::Config>::Call,
+		info: &DispatchInfoOf<<T as frame_system::Config>::Call>,
+		fee: Self::Balance,
+		tip: Self::Balance,
+	) -> Result<Self::LiquidityInfo, TransactionValidityError> {
+		let sponsor = match Pallet::<T>::fee_sponsor(who, call, fee) {
+			Some(sponsor) => sponsor,
+			None => return OCT::withdraw_fee(who, call, info, fee, tip).map(|liquidity| (None, liquidity)),
+		};
+
+		// Release the fee from the budget to withdraw it.
+		T::Currency::unreserve(&sponsor, fee);
+		match OCT::withdraw_fee(&sponsor, call, info, fee, tip) {
+			Ok(liquidity) => {
+				<Sponsorships<T>>::mutate(&sponsor, |sponsorship| if let Some(sponsorship) = sponsorship {
+					sponsorship.budget = sponsorship.budget.saturating_sub(fee);
+				});
+				Ok((Some((sponsor, fee)), liquidity))
+			},
+			Err(err) => {
+				let _ = T::Currency::reserve(&sponsor, fee);
+				Err(err)
+			},
+		}
+	}
+
+	fn correct_and_deposit_fee(
+		who: &T::AccountId,
+		dispatch_info: &DispatchInfoOf<<T as frame_system::Config>::Call>,
+		post_info: &PostDispatchInfoOf<<T as frame_system::Config>::Call>,
+		corrected_fee: Self::Balance,
+		tip: Self::Balance,
+		already_withdrawn: Self::LiquidityInfo,
+	) -> Result<(), TransactionValidityError> {
+		let (sponsor, liquidity) = already_withdrawn;
+		let (sponsor, fee) = match sponsor {
+			Some(sponsor) => sponsor,
+			None => return OCT::correct_and_deposit_fee(who, dispatch_info, post_info, corrected_fee, tip, liquidity),
+		};
+
+		OCT::correct_and_deposit_fee(&sponsor, dispatch_info, post_info, corrected_fee, tip, liquidity)?;
 
+		// Put the refund back into the budget.
+		let refund = fee.saturating_sub(corrected_fee);
+		if !refund.is_zero() && Sponsorships::<T>::contains_key(&sponsor) &&
+			T::Currency::reserve(&sponsor, refund).is_ok() {
+			<Sponsorships<T>>::mutate(&sponsor, |sponsorship| if let Some(sponsorship) = sponsorship {
+				sponsorship.budget = sponsorship.budget.saturating_add(refund);
+			});
+		}
+
+		Ok(())
+	}
+}
@@ -4,19 +4,18 @@ use crate as pallet_connectfour;
 use sp_core::H256;
 
 use frame_support::{
-	parameter_types,
+	parameter_types, PalletId,
 	traits::{OnInitialize, OnFinalize},
-	weights::Weight,
+	weights::IdentityFee,
 };
+use pallet_transaction_payment::CurrencyAdapter;
 
-use frame_support_test::TestRandomness;
 use sp_runtime::{
 	BuildStorage,
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
 	Perbill,
 };
-use frame_system::{EnsureRoot};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -29,7 +28,9 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		Scheduler: pallet_scheduler::{Pallet, Call, Storage, Config, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
+		TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
 		ConnectFour: pallet_connectfour::{Pallet, Call, Config<T>, Storage, Event<T>},
 	}
 );
@@ -59,7 +60,7 @@ impl frame_system::Config for Test {
 	type BlockHashCount = BlockHashCount;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
@@ -68,38 +69,147 @@ impl frame_system::Config for Test {
 }
 
 parameter_types! {
-	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * BlockWeights::get().max_block;
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
 }
-impl pallet_scheduler::Config for Test {
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type Balance = u64;
 	type Event = Event;
-	type Origin = Origin;
-	type PalletsOrigin = OriginCaller;
-	type Call = Call;
-	type MaximumWeight = MaximumSchedulerWeight;
-	type ScheduleOrigin = EnsureRoot<u64>;
-	type MaxScheduledPerBlock = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const TransactionByteFee: u64 = 1;
+}
+impl pallet_transaction_payment::Config for Test {
+	type OnChargeTransaction = SponsoredFees<Test, CurrencyAdapter<Balances, ()>>;
+	type TransactionByteFee = TransactionByteFee;
+	type WeightToFee = IdentityFee<u64>;
+	type FeeMultiplierUpdate = ();
+}
+
+parameter_types! {
+	pub const AssetDeposit: u64 = 0;
+	pub const ApprovalDeposit: u64 = 0;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: u64 = 0;
+	pub const MetadataDepositPerByte: u64 = 0;
+}
+impl pallet_assets::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = StringLimit;
+	type Freezer = ();
+	type Extra = ();
 	type WeightInfo = ();
 }
 
+parameter_types! {
+	pub const ConnectFourPalletId: PalletId = PalletId(*b"py/conn4");
+	pub const BoardDeposit: u64 = 10;
+	pub const MaxDeadlinesPerBlock: u32 = 2;
+	pub const Rake: Perbill = Perbill::from_percent(5);
+	pub const CasualTimeoutSlash: Perbill = Perbill::from_percent(50);
+	pub const WageredTimeoutSlash: Perbill = Perbill::from_percent(100);
+	pub const TimeoutSlashReward: Perbill = Perbill::from_percent(40);
+	pub const MinBet: u64 = 5;
+	pub const MaxBettorsPerBoard: u32 = 3;
+	pub const MarketPeriod: u64 = 5;
+	pub const MarketCloseMoves: u8 = 4;
+	pub const WinReward: u64 = 20;
+	pub const DrawReward: u64 = 5;
+	pub const MinRewardMoves: u8 = 4;
+	pub const RewardOpponentWindow: u32 = 1;
+	pub const RewardEraLength: u64 = 100;
+	pub const MaxEraEmission: u64 = 40;
+	pub const LeaderboardSize: u32 = 3;
+	pub const SeasonLength: u64 = 1_000;
+	pub const SeasonResetFactor: Perbill = Perbill::from_percent(50);
+	pub const MatchWindow: u32 = 50;
+	pub const MatchWindowStep: u32 = 10;
+	pub const MaxQueueSize: u32 = 3;
+	pub const MaxMatchesPerBlock: u32 = 1;
+	pub const MaxQueueComparisons: u32 = 4;
+	pub const MaxOpenChallenges: u32 = 2;
+	pub const TimeoutWindow: u64 = 100;
+	pub const TimeoutThreshold: u32 = 2;
+	pub const BanLength: u64 = 10;
+	pub const MaxFriends: u32 = 2;
+}
 impl pallet_connectfour::Config for Test {
-	type Proposal = Call;
 	type Event = Event;
-	type Randomness = TestRandomness<Self>;
-	type Scheduler = Scheduler;
-	type PalletsOrigin = OriginCaller;
+	type Currency = Balances;
+	type AssetId = u32;
+	type Assets = Assets;
+	type PalletId = ConnectFourPalletId;
+	type BoardDeposit = BoardDeposit;
+	type MaxDeadlinesPerBlock = MaxDeadlinesPerBlock;
+	type Rake = Rake;
+	type CasualTimeoutSlash = CasualTimeoutSlash;
+	type WageredTimeoutSlash = WageredTimeoutSlash;
+	type TimeoutSlashReward = TimeoutSlashReward;
+	type MinBet = MinBet;
+	type MaxBettorsPerBoard = MaxBettorsPerBoard;
+	type MarketPeriod = MarketPeriod;
+	type MarketCloseMoves = MarketCloseMoves;
+	type WinReward = WinReward;
+	type DrawReward = DrawReward;
+	type MinRewardMoves = MinRewardMoves;
+	type RewardOpponentWindow = RewardOpponentWindow;
+	type RewardEraLength = RewardEraLength;
+	type MaxEraEmission = MaxEraEmission;
+	type LeaderboardSize = LeaderboardSize;
+	type SeasonLength = SeasonLength;
+	type SeasonResetFactor = SeasonResetFactor;
+	type MatchWindow = MatchWindow;
+	type MatchWindowStep = MatchWindowStep;
+	type MaxQueueSize = MaxQueueSize;
+	type MaxMatchesPerBlock = MaxMatchesPerBlock;
+	type MaxQueueComparisons = MaxQueueComparisons;
+	type MaxOpenChallenges = MaxOpenChallenges;
+	type TimeoutWindow = TimeoutWindow;
+	type TimeoutThreshold = TimeoutThreshold;
+	type BanLength = BanLength;
+	type MaxFriends = MaxFriends;
 }
 
+/// Balance every test account starts with.
+pub const INITIAL_BALANCE: u64 = 1_000;
+
+/// Founder key receiving the platform rake and timeout slashes.
+pub const FOUNDER: u64 = 9;
+
 /// Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
 	//frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
 	let t = GenesisConfig {
 			frame_system: Default::default(),
-			pallet_scheduler: Default::default(),
-			pallet_connectfour: Default::default(),
+			pallet_balances: pallet_balances::GenesisConfig {
+				balances: (1..=6).chain(Some(FOUNDER)).map(|who| (who, INITIAL_BALANCE)).collect(),
+			},
+			pallet_connectfour: pallet_connectfour::GenesisConfig {
+				founder_key: FOUNDER,
+			},
 		}.build_storage().unwrap();
 		t.into()
 }
 
+/// Get the last event deposited in the mock runtime.
+pub fn last_event() -> Event {
+	System::events().pop().expect("Event expected").event
+}
+
 pub fn run_next_block() {
 	run_to_block(System::block_number() + 1);
 }
@@ -111,7 +221,6 @@ pub fn run_to_block(n: u64) {
 		if System::block_number() > 1 {
 			// mock on_finalize
 			System::on_finalize(System::block_number());
-			Scheduler::on_finalize(System::block_number());
 			ConnectFour::on_finalize(System::block_number());
 		}
 
@@ -119,7 +228,6 @@ pub fn run_to_block(n: u64) {
 		
 		// mock on_initialize
 		System::on_initialize(System::block_number());
-		Scheduler::on_initialize(System::block_number());
 		ConnectFour::on_initialize(System::block_number());
 	}
 }
\ No newline at end of file
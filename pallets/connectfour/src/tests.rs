@@ -1,7 +1,43 @@
 use super::*;
 use crate::{Error, mock::*};
 
-use frame_support::{assert_ok, assert_noop};
+use frame_support::{assert_ok, assert_noop, traits::{LockableCurrency, WithdrawReasons}, weights::{DispatchInfo, Pays}};
+use sp_core::H256;
+use sp_runtime::traits::BlakeTwo256;
+
+/// Create a board between two players and run its coin toss, returns the board id.
+fn start_game(red: u64, blue: u64) -> H256 {
+	start_wager_game(red, blue, 0)
+}
+
+/// Create a wagered board between two players and run its coin toss, returns the board id.
+fn start_wager_game(red: u64, blue: u64, stake: u64) -> H256 {
+	start_asset_game(red, blue, stake, None)
+}
+
+/// Create a board wagered in an asset and run its coin toss, returns the board id.
+fn start_asset_game(red: u64, blue: u64, stake: u64, asset: Option<u32>) -> H256 {
+	assert_ok!(ConnectFour::new_game(Origin::signed(red), blue, stake, asset));
+	let board_id = ConnectFour::player_board(red);
+
+	let (red_secret, blue_secret) = (H256::repeat_byte(red as u8), H256::repeat_byte(blue as u8));
+	assert_ok!(ConnectFour::commit_toss(Origin::signed(red), BlakeTwo256::hash_of(&(red, red_secret))));
+	assert_ok!(ConnectFour::commit_toss(Origin::signed(blue), BlakeTwo256::hash_of(&(blue, blue_secret))));
+	assert_ok!(ConnectFour::reveal_toss(Origin::signed(red), red_secret));
+	assert_ok!(ConnectFour::reveal_toss(Origin::signed(blue), blue_secret));
+	assert!(!CoinTosses::<Test>::contains_key(board_id));
+
+	board_id
+}
+
+/// Place stones in the given columns, alternating between the players of a board.
+fn play_moves(board_id: H256, columns: &[u8]) {
+	for column in columns {
+		let board = ConnectFour::boards(board_id);
+		let player = if board.next_player == PLAYER_1 { board.red } else { board.blue };
+		assert_ok!(ConnectFour::play_turn(Origin::signed(player), *column));
+	}
+}
 
 #[test]
 fn it_works_for_default_value() {
@@ -30,12 +66,12 @@ fn test_game_creation() {
 
 		// Test player can not play against himself
 		assert_noop!(
-			ConnectFour::new_game(Origin::signed(1), 1),
+			ConnectFour::new_game(Origin::signed(1), 1, 0, None),
 			Error::<Test>::NoFakePlay
 		);
 
 		// Test game creation between to different players
-		assert_ok!(ConnectFour::new_game(Origin::signed(1), 2));
+		assert_ok!(ConnectFour::new_game(Origin::signed(1), 2, 0, None));
 		run_to_block(1);
 
 		let board_id_1 = ConnectFour::player_board(1);
@@ -44,12 +80,12 @@ fn test_game_creation() {
 		assert_eq!(board_id_1, board_id_2);
 
 		assert_noop!(
-			ConnectFour::new_game(Origin::signed(1), 3),
+			ConnectFour::new_game(Origin::signed(1), 3, 0, None),
 			Error::<Test>::PlayerBoardExists
 		);
 
 		assert_noop!(
-			ConnectFour::new_game(Origin::signed(3), 2),
+			ConnectFour::new_game(Origin::signed(3), 2, 0, None),
 			Error::<Test>::PlayerBoardExists
 		);
 
@@ -70,8 +106,7 @@ fn test_game_play() {
 		run_to_block(current_block);
 
 		// Test game creation between to different players
-		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64));
-		let board_id = ConnectFour::player_board(PLAYER_1 as u64);
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
 		let board = ConnectFour::boards(board_id);
 		assert_eq!(board.last_turn, current_block);
 
@@ -143,32 +178,6 @@ fn test_game_play() {
 	});
 }
 
-#[test]
-fn test_game_events() {
-	new_test_ext().execute_with(|| {
-
-		let blocks_to_pass = 10;
-		let mut current_block:u64 = 100;
-	
-		// start from block 100
-		run_to_block(current_block);
-
-		assert_eq!(None, ConnectFour::something());
-
-		// Test game creation between to different players
-		assert_ok!(ConnectFour::test_schedule(Origin::signed(PLAYER_1 as u64), blocks_to_pass));
-		
-		run_next_block();
-		current_block = current_block + 1;
-
-		assert_eq!(None, ConnectFour::something());
-
-		run_to_block(current_block + blocks_to_pass);
-
-		assert_eq!(77, ConnectFour::something().unwrap());
-	});
-}
-
 #[test]
 fn test_force_turn() {
 	new_test_ext().execute_with(|| {
@@ -179,8 +188,7 @@ fn test_force_turn() {
 		run_to_block(current_block);
 
 		// Test game creation between to different players
-		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64));
-		let board_id = ConnectFour::player_board(PLAYER_1 as u64);
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
 		let board = ConnectFour::boards(board_id);
 		assert_eq!(board.last_turn, current_block);
 
@@ -228,4 +236,1492 @@ fn test_force_turn() {
 		assert!(!PlayerBoard::<Test>::contains_key(board.blue));
 		assert!(!BoardSchedules::<Test>::contains_key(board_id));
 	});
-}
\ No newline at end of file
+}
+#[test]
+fn test_board_deposit() {
+	new_test_ext().execute_with(|| {
+
+		let mut current_block:u64 = 100;
+
+		// start from block 100
+		run_to_block(current_block);
+
+		// Creating and committing to a board reserves the deposit from both players
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		let board = ConnectFour::boards(board_id);
+		assert_eq!(Balances::reserved_balance(PLAYER_1 as u64), BoardDeposit::get());
+		assert_eq!(Balances::reserved_balance(PLAYER_2 as u64), BoardDeposit::get());
+		assert_eq!(ConnectFour::board_deposits(board_id, PLAYER_1 as u64), BoardDeposit::get());
+		assert_eq!(ConnectFour::board_deposits(board_id, PLAYER_2 as u64), BoardDeposit::get());
+
+		run_next_block();
+		current_block = current_block + 1;
+
+		// Only the starting player places a stone, the other one abandons the game
+		let (player, idle) = if board.next_player == PLAYER_1 {
+			(PLAYER_1 as u64, PLAYER_2 as u64)
+		} else {
+			(PLAYER_2 as u64, PLAYER_1 as u64)
+		};
+		assert_ok!(ConnectFour::play_turn(Origin::signed(player), 0));
+
+		run_to_block(current_block + 10 + 20);
+
+		// check if the deposit got released or slashed on cleanup, the idle player got slashed on
+		// the timeout already
+		let reward = TimeoutSlashReward::get() * (CasualTimeoutSlash::get() * BoardDeposit::get());
+		assert!(!Boards::<Test>::contains_key(board_id));
+		assert!(!BoardDeposits::<Test>::contains_key(board_id, idle));
+		assert_eq!(Balances::reserved_balance(player), 0);
+		assert_eq!(Balances::free_balance(player), INITIAL_BALANCE + reward);
+		assert_eq!(Balances::reserved_balance(idle), 0);
+		assert_eq!(Balances::free_balance(idle), INITIAL_BALANCE - BoardDeposit::get());
+	});
+}
+
+#[test]
+fn test_board_deposit_insufficient_balance() {
+	new_test_ext().execute_with(|| {
+
+		// Account 7 has no funds to reserve the deposit
+		assert_noop!(
+			ConnectFour::new_game(Origin::signed(7), 1, 0, None),
+			Error::<Test>::InsufficientDeposit
+		);
+		assert_ok!(ConnectFour::new_game(Origin::signed(1), 7, 0, None));
+		assert_noop!(
+			ConnectFour::commit_toss(Origin::signed(7), H256::repeat_byte(7)),
+			Error::<Test>::InsufficientDeposit
+		);
+	});
+}
+
+#[test]
+fn test_uncommitted_board_is_voided() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		// Only the creator pays the deposit until the opponent commits
+		assert_ok!(ConnectFour::new_game(Origin::signed(1), 2, 0, None));
+		let board_id = ConnectFour::player_board(1);
+		assert_eq!(Balances::reserved_balance(1), BoardDeposit::get());
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_ok!(ConnectFour::commit_toss(Origin::signed(1), H256::repeat_byte(1)));
+
+		// The board the opponent never committed to is voided without a result
+		run_to_block(100 + 10);
+		assert!(!Boards::<Test>::contains_key(board_id));
+		assert!(!PlayerBoard::<Test>::contains_key(2));
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(2), INITIAL_BALANCE);
+		assert_eq!(ConnectFour::ratings(2), Rating::default());
+		assert_eq!(ConnectFour::player_stats(2).games, 0);
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::GameVoided(board_id))
+		));
+	});
+}
+
+#[test]
+fn test_unstarted_game_times_out() {
+	new_test_ext().execute_with(|| {
+
+		let current_block:u64 = 100;
+
+		// start from block 100
+		run_to_block(current_block);
+
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		assert_eq!(ConnectFour::board_schedules(board_id), Some(current_block + 10));
+
+		// nobody moved after the toss, the player waiting for the first turn wins
+		run_to_block(current_block + 10);
+		let board = ConnectFour::boards(board_id);
+		if board.next_player == PLAYER_1 {
+			assert!(board.board_state == BoardState::Finished(board.blue));
+		} else {
+			assert!(board.board_state == BoardState::Finished(board.red));
+		}
+		assert_eq!(ConnectFour::board_schedules(board_id), Some(current_block + 10 + 20));
+
+		// On cleanup the winner gets the whole deposit back, the staller loses the rest of theirs
+		let (winner, staller) = if board.next_player == PLAYER_1 {
+			(board.blue, board.red)
+		} else {
+			(board.red, board.blue)
+		};
+		let reward = TimeoutSlashReward::get() * (CasualTimeoutSlash::get() * BoardDeposit::get());
+		run_to_block(current_block + 10 + 20);
+		assert!(!Boards::<Test>::contains_key(board_id));
+		assert_eq!(Balances::reserved_balance(winner), 0);
+		assert_eq!(Balances::free_balance(winner), INITIAL_BALANCE + reward);
+		assert_eq!(Balances::reserved_balance(staller), 0);
+		assert_eq!(Balances::free_balance(staller), INITIAL_BALANCE - BoardDeposit::get());
+	});
+}
+
+#[test]
+fn test_deadlines_carry_over() {
+	new_test_ext().execute_with(|| {
+
+		let current_block:u64 = 100;
+
+		// start from block 100
+		run_to_block(current_block);
+
+		// Create more boards expiring at the same block than handled per block
+		assert_ok!(ConnectFour::new_game(Origin::signed(1), 2, 0, None));
+		assert_ok!(ConnectFour::new_game(Origin::signed(3), 4, 0, None));
+		assert_ok!(ConnectFour::new_game(Origin::signed(5), 6, 0, None));
+		let board_ids = [ConnectFour::player_board(1), ConnectFour::player_board(3), ConnectFour::player_board(5)];
+
+		run_to_block(current_block + 10);
+
+		// the last board is carried over to the next block
+		assert!(ConnectFour::boards(board_ids[0]).board_state != BoardState::Running);
+		assert!(ConnectFour::boards(board_ids[1]).board_state != BoardState::Running);
+		assert!(ConnectFour::boards(board_ids[2]).board_state == BoardState::Running);
+		assert_eq!(ConnectFour::deadlines(current_block + 11), vec![board_ids[2]]);
+
+		run_next_block();
+
+		assert!(ConnectFour::boards(board_ids[2]).board_state != BoardState::Running);
+		assert!(!Deadlines::<Test>::contains_key(current_block + 11));
+
+		// The bettors of a board count against the budget as well
+		let current_block:u64 = 200;
+		run_to_block(current_block);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(1), 2, 0, None));
+		assert_ok!(ConnectFour::new_game(Origin::signed(3), 4, 0, None));
+		let board_ids = [ConnectFour::player_board(1), ConnectFour::player_board(3)];
+		assert_ok!(ConnectFour::place_bet(Origin::signed(5), board_ids[0], 1, 100));
+
+		run_to_block(current_block + 10);
+
+		assert!(!Boards::<Test>::contains_key(board_ids[0]));
+		assert_eq!(Balances::reserved_balance(5), 0);
+		assert_eq!(ConnectFour::deadlines(current_block + 11), vec![board_ids[1]]);
+
+		run_next_block();
+
+		assert!(!Boards::<Test>::contains_key(board_ids[1]));
+	});
+}
+
+#[test]
+fn test_force_end_turn_checks_last_turn() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64, 0, None));
+		let board_id = ConnectFour::player_board(PLAYER_1 as u64);
+
+		assert_noop!(
+			ConnectFour::force_end_turn(Origin::signed(PLAYER_1 as u64), board_id, 100),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			ConnectFour::force_end_turn(Origin::root(), board_id, 99),
+			"There has been a move in between."
+		);
+		assert_ok!(ConnectFour::force_end_turn(Origin::root(), board_id, 100));
+		assert!(ConnectFour::boards(board_id).board_state != BoardState::Running);
+	});
+}
+
+#[test]
+fn test_claim_timeout() {
+	new_test_ext().execute_with(|| {
+
+		let current_block:u64 = 100;
+
+		// start from block 100
+		run_to_block(current_block);
+
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		let board = ConnectFour::boards(board_id);
+		let (at_turn, waiting) = if board.next_player == PLAYER_1 {
+			(board.red, board.blue)
+		} else {
+			(board.blue, board.red)
+		};
+
+		assert_noop!(
+			ConnectFour::claim_timeout(Origin::signed(waiting), board_id, current_block),
+			Error::<Test>::TurnNotExpired
+		);
+
+		// skip the deadline without running the hooks, like a stalled deadline queue
+		System::set_block_number(current_block + 10);
+
+		assert_noop!(
+			ConnectFour::claim_timeout(Origin::signed(waiting), board_id, current_block - 1),
+			"There has been a move in between."
+		);
+		assert_noop!(
+			ConnectFour::claim_timeout(Origin::signed(at_turn), board_id, current_block),
+			Error::<Test>::NotWaitingPlayer
+		);
+		assert_noop!(
+			ConnectFour::claim_timeout(Origin::signed(3), board_id, current_block),
+			Error::<Test>::NotWaitingPlayer
+		);
+
+		assert_ok!(ConnectFour::claim_timeout(Origin::signed(waiting), board_id, current_block));
+		let board = ConnectFour::boards(board_id);
+		assert!(board.board_state == BoardState::Finished(waiting));
+		assert_eq!(ConnectFour::board_schedules(board_id), Some(current_block + 10 + 20));
+	});
+}
+
+#[test]
+fn test_void_game() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64, 0, None));
+		let board_id = ConnectFour::player_board(PLAYER_1 as u64);
+
+		assert_noop!(
+			ConnectFour::void_game(Origin::signed(PLAYER_1 as u64), board_id),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(ConnectFour::void_game(Origin::root(), board_id));
+
+		// check if the board is gone and nobody lost the deposit
+		assert!(!Boards::<Test>::contains_key(board_id));
+		assert!(!PlayerBoard::<Test>::contains_key(PLAYER_1 as u64));
+		assert!(!PlayerBoard::<Test>::contains_key(PLAYER_2 as u64));
+		assert!(!BoardSchedules::<Test>::contains_key(board_id));
+		assert_eq!(Balances::free_balance(PLAYER_1 as u64), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(PLAYER_2 as u64), INITIAL_BALANCE);
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::GameVoided(board_id)));
+	});
+}
+
+#[test]
+fn test_force_finish() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64, 0, None));
+		let board_id = ConnectFour::player_board(PLAYER_1 as u64);
+
+		assert_noop!(
+			ConnectFour::force_finish(Origin::root(), board_id, Some(3)),
+			Error::<Test>::NotBoardPlayer
+		);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(PLAYER_2 as u64)));
+
+		let board = ConnectFour::boards(board_id);
+		assert!(board.board_state == BoardState::Finished(PLAYER_2 as u64));
+		assert_eq!(ConnectFour::board_schedules(board_id), Some(100 + 20));
+
+		// The coin toss of the finished board is gone
+		assert!(!CoinTosses::<Test>::contains_key(board_id));
+		assert_noop!(
+			ConnectFour::commit_toss(Origin::signed(PLAYER_1 as u64), H256::repeat_byte(1)),
+			Error::<Test>::NoCoinToss
+		);
+		assert_eq!(
+			last_event(),
+			mock::Event::pallet_connectfour(crate::Event::GameForceFinished(board_id, Some(PLAYER_2 as u64)))
+		);
+
+		assert_noop!(
+			ConnectFour::force_finish(Origin::root(), board_id, None),
+			"Board is not running, check if already finished."
+		);
+	});
+}
+
+#[test]
+fn test_purge_orphans() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let orphan_board = H256::repeat_byte(1);
+		PlayerBoard::<Test>::insert(3, orphan_board);
+		BoardSchedules::<Test>::insert(orphan_board, 10);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64, 0, None));
+		let board_id = ConnectFour::player_board(PLAYER_1 as u64);
+
+		assert_ok!(ConnectFour::purge_orphans(Origin::root(), vec![PLAYER_1 as u64, 3], vec![board_id, orphan_board]));
+
+		// only the entries pointing at the missing board got removed
+		assert!(!PlayerBoard::<Test>::contains_key(3));
+		assert!(!BoardSchedules::<Test>::contains_key(orphan_board));
+		assert!(PlayerBoard::<Test>::contains_key(PLAYER_1 as u64));
+		assert!(BoardSchedules::<Test>::contains_key(board_id));
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::OrphansPurged(1, 1)));
+
+		// Deposits, stakes and bets left on an orphaned board are returned
+		let (red, blue, stake) = (4, 5, 100);
+		assert_ok!(ConnectFour::new_game(Origin::signed(red), blue, stake, None));
+		let wagered_board = ConnectFour::player_board(red);
+		assert_ok!(ConnectFour::commit_toss(Origin::signed(red), H256::repeat_byte(1)));
+		assert_ok!(ConnectFour::commit_toss(Origin::signed(blue), H256::repeat_byte(2)));
+		assert_ok!(ConnectFour::place_bet(Origin::signed(3), wagered_board, red, 100));
+		Boards::<Test>::remove(wagered_board);
+
+		assert_ok!(ConnectFour::purge_orphans(Origin::root(), vec![], vec![wagered_board]));
+		for account in [red, blue, 3].iter() {
+			assert_eq!(Balances::reserved_balance(account), 0);
+			assert_eq!(Balances::free_balance(account), INITIAL_BALANCE);
+		}
+		assert!(!Wagers::<Test>::contains_key(wagered_board));
+		assert!(!Markets::<Test>::contains_key(wagered_board));
+		assert!(!CoinTosses::<Test>::contains_key(wagered_board));
+		assert!(!BoardSettings::<Test>::contains_key(wagered_board));
+		assert!(!BoardNumbers::<Test>::contains_key(wagered_board));
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::OrphansPurged(0, 1)));
+	});
+}
+
+#[test]
+fn test_coin_toss() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64, 0, None));
+		let board_id = ConnectFour::player_board(PLAYER_1 as u64);
+		let (red_secret, blue_secret) = (H256::repeat_byte(7), H256::repeat_byte(9));
+
+		// Nobody can play before the toss decided the starting player
+		assert_noop!(
+			ConnectFour::play_turn(Origin::signed(PLAYER_1 as u64), 0),
+			Error::<Test>::TossPending
+		);
+
+		assert_ok!(ConnectFour::commit_toss(Origin::signed(PLAYER_1 as u64), BlakeTwo256::hash_of(&(1u64, red_secret))));
+		assert_noop!(
+			ConnectFour::commit_toss(Origin::signed(PLAYER_1 as u64), BlakeTwo256::hash_of(&(1u64, red_secret))),
+			Error::<Test>::AlreadyCommitted
+		);
+		assert_noop!(
+			ConnectFour::reveal_toss(Origin::signed(PLAYER_1 as u64), red_secret),
+			Error::<Test>::TossNotCommitted
+		);
+
+		run_next_block();
+
+		// The reveal phase gets its own deadline once both committed
+		assert_ok!(ConnectFour::commit_toss(Origin::signed(PLAYER_2 as u64), BlakeTwo256::hash_of(&(2u64, blue_secret))));
+		assert_eq!(ConnectFour::board_schedules(board_id), Some(101 + 10));
+
+		// A secret copied from the opponent doesn't match the commitment
+		assert_noop!(
+			ConnectFour::reveal_toss(Origin::signed(PLAYER_2 as u64), red_secret),
+			Error::<Test>::InvalidReveal
+		);
+		assert_ok!(ConnectFour::reveal_toss(Origin::signed(PLAYER_2 as u64), blue_secret));
+		assert_noop!(
+			ConnectFour::reveal_toss(Origin::signed(PLAYER_2 as u64), blue_secret),
+			Error::<Test>::AlreadyRevealed
+		);
+		assert_ok!(ConnectFour::reveal_toss(Origin::signed(PLAYER_1 as u64), red_secret));
+
+		// The combined secrets decide the starting player
+		let coin = BlakeTwo256::hash_of(&(red_secret, blue_secret));
+		let (next_player, starting_account) = if coin.as_ref()[0] < 128 {
+			(PLAYER_1, PLAYER_1 as u64)
+		} else {
+			(PLAYER_2, PLAYER_2 as u64)
+		};
+		let board = ConnectFour::boards(board_id);
+		assert_eq!(board.next_player, next_player);
+		assert_eq!(board.last_turn, 101);
+		assert!(!CoinTosses::<Test>::contains_key(board_id));
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::TossDecided(board_id, starting_account)));
+
+		assert_noop!(
+			ConnectFour::reveal_toss(Origin::signed(PLAYER_1 as u64), red_secret),
+			Error::<Test>::NoCoinToss
+		);
+	});
+}
+
+#[test]
+fn test_coin_toss_forfeit() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64, 0, None));
+		let board_id = ConnectFour::player_board(PLAYER_1 as u64);
+		let (red_secret, blue_secret) = (H256::repeat_byte(7), H256::repeat_byte(9));
+
+		assert_ok!(ConnectFour::commit_toss(Origin::signed(PLAYER_1 as u64), BlakeTwo256::hash_of(&(1u64, red_secret))));
+		assert_ok!(ConnectFour::commit_toss(Origin::signed(PLAYER_2 as u64), BlakeTwo256::hash_of(&(2u64, blue_secret))));
+		assert_ok!(ConnectFour::reveal_toss(Origin::signed(PLAYER_1 as u64), red_secret));
+
+		// The player refusing to reveal forfeits the game
+		run_to_block(100 + 10);
+		let board = ConnectFour::boards(board_id);
+		assert!(board.board_state == BoardState::Finished(PLAYER_1 as u64));
+		assert!(!CoinTosses::<Test>::contains_key(board_id));
+
+		// The player who revealed keeps the whole deposit without placing a stone
+		let reward = TimeoutSlashReward::get() * (CasualTimeoutSlash::get() * BoardDeposit::get());
+		run_to_block(100 + 10 + 20);
+		assert_eq!(Balances::reserved_balance(PLAYER_1 as u64), 0);
+		assert_eq!(Balances::free_balance(PLAYER_1 as u64), INITIAL_BALANCE + reward);
+		assert_eq!(Balances::reserved_balance(PLAYER_2 as u64), 0);
+		assert_eq!(Balances::free_balance(PLAYER_2 as u64), INITIAL_BALANCE - BoardDeposit::get());
+	});
+}
+
+#[test]
+fn test_board_ids() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		// Clients can compute the id of the board they are about to create
+		let board_id = ConnectFour::next_board_id(&1);
+		assert_eq!(board_id, BlakeTwo256::hash_of(&(*b"connect4", 1u64, 0u64)));
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(1), 2, 0, None));
+		assert_eq!(ConnectFour::player_board(1), board_id);
+		assert_eq!(ConnectFour::board_nonce(1), 1);
+		assert_ne!(ConnectFour::next_board_id(&1), board_id);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(3), 4, 0, None));
+		let other_board_id = ConnectFour::player_board(3);
+
+		// Boards get sequential game numbers with lookup in both directions
+		assert_eq!(ConnectFour::game_count(), 2);
+		assert_eq!(ConnectFour::board_number(board_id), Some(1));
+		assert_eq!(ConnectFour::board_number(other_board_id), Some(2));
+		assert_eq!(ConnectFour::game_board(1), Some(board_id));
+		assert_eq!(ConnectFour::game_board(2), Some(other_board_id));
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::NewBoard(other_board_id, 2)));
+
+		// The numbers are released together with the board
+		assert_ok!(ConnectFour::void_game(Origin::root(), board_id));
+		assert_eq!(ConnectFour::board_number(board_id), None);
+		assert_eq!(ConnectFour::game_board(1), None);
+	});
+}
+
+#[test]
+fn test_board_id_collision() {
+	new_test_ext().execute_with(|| {
+
+		// A live board with the next id is never overwritten
+		let board_id = ConnectFour::next_board_id(&1);
+		Boards::<Test>::insert(board_id, BoardStruct::default());
+
+		assert_noop!(
+			ConnectFour::new_game(Origin::signed(1), 2, 0, None),
+			Error::<Test>::BoardExists
+		);
+	});
+}
+
+#[test]
+fn test_check_move_extension() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		let board = ConnectFour::boards(board_id);
+		let (at_turn, waiting) = if board.next_player == PLAYER_1 {
+			(board.red, board.blue)
+		} else {
+			(board.blue, board.red)
+		};
+
+		let info = DispatchInfo::default();
+		let play_turn = |column| mock::Call::ConnectFour(crate::Call::play_turn(column));
+
+		// Moves out of turn or into illegal columns never enter the pool
+		assert_eq!(
+			CheckMove::<Test>::new().validate(&waiting, &play_turn(0), &info, 0),
+			InvalidTransaction::Call.into()
+		);
+		assert_eq!(
+			CheckMove::<Test>::new().validate(&at_turn, &play_turn(7), &info, 0),
+			InvalidTransaction::Call.into()
+		);
+		assert_eq!(
+			CheckMove::<Test>::new().validate(&3, &play_turn(0), &info, 0),
+			InvalidTransaction::Call.into()
+		);
+
+		// Competing moves of the same turn provide the same tag
+		let first = CheckMove::<Test>::new().validate(&at_turn, &play_turn(0), &info, 0).unwrap();
+		let second = CheckMove::<Test>::new().validate(&at_turn, &play_turn(1), &info, 0).unwrap();
+		assert_eq!(first.provides, second.provides);
+
+		assert_ok!(ConnectFour::play_turn(Origin::signed(at_turn), 0));
+		let next = CheckMove::<Test>::new().validate(&waiting, &play_turn(0), &info, 0).unwrap();
+		assert_ne!(first.provides, next.provides);
+
+		// Other calls pass through untouched
+		assert_eq!(
+			CheckMove::<Test>::new().validate(&3, &mock::Call::ConnectFour(crate::Call::do_something(1)), &info, 0),
+			Ok(ValidTransaction::default())
+		);
+	});
+}
+
+#[test]
+fn test_play_turn_invalid_column() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		let board = ConnectFour::boards(board_id);
+		let at_turn = if board.next_player == PLAYER_1 { board.red } else { board.blue };
+
+		assert_noop!(
+			ConnectFour::play_turn(Origin::signed(at_turn), 7),
+			Error::<Test>::InvalidColumn
+		);
+	});
+}
+
+#[test]
+fn test_moves_are_free() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		let board = ConnectFour::boards(board_id);
+		let (mut at_turn, mut waiting) = if board.next_player == PLAYER_1 {
+			(board.red, board.blue)
+		} else {
+			(board.blue, board.red)
+		};
+
+		// Play a vertical line for the starting player, every move including the last one is free
+		for column in [0, 1, 0, 1, 0, 1, 0].iter() {
+			let post_info = ConnectFour::play_turn(Origin::signed(at_turn), *column).unwrap();
+			assert_eq!(post_info.pays_fee, Pays::No);
+			sp_std::mem::swap(&mut at_turn, &mut waiting);
+		}
+		assert!(ConnectFour::boards(board_id).board_state == BoardState::Finished(waiting));
+
+		// Rejected moves still pay
+		let err = ConnectFour::play_turn(Origin::signed(at_turn), 2).unwrap_err();
+		assert_eq!(err.post_info.pays_fee, Pays::Yes);
+	});
+}
+
+#[test]
+fn test_move_keys() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		let board = ConnectFour::boards(board_id);
+		let (at_turn, waiting) = if board.next_player == PLAYER_1 {
+			(board.red, board.blue)
+		} else {
+			(board.blue, board.red)
+		};
+		let (key, waiting_key) = (5, 6);
+
+		// Keys can't be players or serve two players
+		assert_noop!(
+			ConnectFour::authorize_move_key(Origin::signed(at_turn), waiting, None),
+			Error::<Test>::MoveKeyInUse
+		);
+		assert_ok!(ConnectFour::authorize_move_key(Origin::signed(at_turn), key, None));
+		assert_noop!(
+			ConnectFour::authorize_move_key(Origin::signed(waiting), key, None),
+			Error::<Test>::MoveKeyInUse
+		);
+		assert_ok!(ConnectFour::authorize_move_key(Origin::signed(waiting), waiting_key, Some(101)));
+
+		// The session key moves on behalf of its owner
+		assert_ok!(ConnectFour::play_turn(Origin::signed(key), 0));
+		let board = ConnectFour::boards(board_id);
+		assert_eq!(board.board[0][5], if at_turn == board.red { PLAYER_1 } else { PLAYER_2 });
+
+		// Expired keys can't move anymore
+		run_to_block(102);
+		assert_noop!(
+			ConnectFour::play_turn(Origin::signed(waiting_key), 0),
+			Error::<Test>::NoPlayerBoard
+		);
+
+		// Revoked keys can't move anymore
+		assert_ok!(ConnectFour::revoke_move_key(Origin::signed(waiting)));
+		assert_noop!(ConnectFour::revoke_move_key(Origin::signed(waiting)), Error::<Test>::NoMoveKey);
+		assert!(!MoveKeys::<Test>::contains_key(waiting_key));
+
+		// The remaining key is removed together with the board
+		assert_ok!(ConnectFour::void_game(Origin::root(), board_id));
+		assert!(!MoveKeys::<Test>::contains_key(key));
+		assert!(!PlayerMoveKey::<Test>::contains_key(at_turn));
+	});
+}
+
+#[test]
+fn test_wager_win() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let stake = 100;
+		let board_id = start_wager_game(PLAYER_1 as u64, PLAYER_2 as u64, stake);
+
+		// Both players reserved the stake when committing
+		assert_eq!(ConnectFour::wagers(board_id), Some(Wager { asset: None, stake }));
+		assert_eq!(ConnectFour::board_stakes(board_id, PLAYER_1 as u64), stake);
+		assert_eq!(Balances::reserved_balance(PLAYER_1 as u64), BoardDeposit::get() + stake);
+		assert_eq!(Balances::reserved_balance(PLAYER_2 as u64), BoardDeposit::get() + stake);
+
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(PLAYER_2 as u64)));
+
+		// The winner received the stake of the loser minus the rake and the stakes are released
+		let rake = Rake::get() * (2 * stake);
+		assert!(!Wagers::<Test>::contains_key(board_id));
+		assert!(!BoardStakes::<Test>::contains_key(board_id, PLAYER_2 as u64));
+		assert_eq!(Balances::free_balance(PLAYER_1 as u64), INITIAL_BALANCE - BoardDeposit::get() - stake);
+		assert_eq!(Balances::free_balance(PLAYER_2 as u64), INITIAL_BALANCE - BoardDeposit::get() + stake - rake);
+		assert_eq!(Balances::free_balance(FOUNDER), INITIAL_BALANCE + rake);
+		assert_eq!(Balances::reserved_balance(PLAYER_1 as u64), BoardDeposit::get());
+		assert_eq!(Balances::reserved_balance(PLAYER_2 as u64), BoardDeposit::get());
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::RakePaid(board_id, FOUNDER, rake))
+		));
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::WagerPaid(board_id, PLAYER_2 as u64, stake - rake))
+		));
+	});
+}
+
+#[test]
+fn test_wager_draw_and_void() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let stake = 100;
+
+		// A draw returns the stakes
+		let board_id = start_wager_game(PLAYER_1 as u64, PLAYER_2 as u64, stake);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, None));
+		assert_eq!(Balances::free_balance(PLAYER_1 as u64), INITIAL_BALANCE - BoardDeposit::get());
+		assert_eq!(Balances::free_balance(PLAYER_2 as u64), INITIAL_BALANCE - BoardDeposit::get());
+		assert_eq!(Balances::reserved_balance(PLAYER_1 as u64), BoardDeposit::get());
+		assert_eq!(Balances::reserved_balance(PLAYER_2 as u64), BoardDeposit::get());
+
+		// A voided board returns the stakes
+		let board_id = start_wager_game(3, 4, stake);
+		assert_ok!(ConnectFour::void_game(Origin::root(), board_id));
+		assert!(!Wagers::<Test>::contains_key(board_id));
+		assert_eq!(Balances::free_balance(3), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(4), INITIAL_BALANCE);
+		assert_eq!(Balances::reserved_balance(3), 0);
+	});
+}
+
+#[test]
+fn test_wager_timeout() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let stake = 100;
+		let board_id = start_wager_game(PLAYER_1 as u64, PLAYER_2 as u64, stake);
+		let board = ConnectFour::boards(board_id);
+		let (at_turn, waiting) = if board.next_player == PLAYER_1 {
+			(board.red, board.blue)
+		} else {
+			(board.blue, board.red)
+		};
+
+		// The player kept waiting receives the stake and part of the slashed deposit
+		run_to_block(100 + 10);
+		let rake = Rake::get() * (2 * stake);
+		let slash = WageredTimeoutSlash::get() * BoardDeposit::get();
+		let reward = TimeoutSlashReward::get() * slash;
+		assert_eq!(Balances::free_balance(waiting), INITIAL_BALANCE - BoardDeposit::get() + stake - rake + reward);
+		assert_eq!(Balances::free_balance(at_turn), INITIAL_BALANCE - BoardDeposit::get() - stake);
+		assert_eq!(Balances::reserved_balance(at_turn), BoardDeposit::get() - slash);
+		assert_eq!(Balances::free_balance(FOUNDER), INITIAL_BALANCE + rake + slash - reward);
+
+		// The winner gets the deposit back on cleanup
+		run_to_block(100 + 10 + 20);
+		assert_eq!(Balances::reserved_balance(waiting), 0);
+		assert_eq!(Balances::free_balance(waiting), INITIAL_BALANCE + stake - rake + reward);
+		assert_eq!(Balances::reserved_balance(at_turn), 0);
+		assert_eq!(Balances::free_balance(at_turn), INITIAL_BALANCE - BoardDeposit::get() - stake);
+	});
+}
+
+#[test]
+fn test_wager_insufficient_stake() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		assert_ok!(ConnectFour::new_game(Origin::signed(PLAYER_1 as u64), PLAYER_2 as u64, INITIAL_BALANCE, None));
+		assert_noop!(
+			ConnectFour::commit_toss(Origin::signed(PLAYER_2 as u64), H256::repeat_byte(1)),
+			Error::<Test>::InsufficientStake
+		);
+
+		// Funds locked by other pallets can't be staked
+		assert_ok!(ConnectFour::new_game(Origin::signed(3), 4, 100, None));
+		Balances::set_lock(*b"vesting ", &4, INITIAL_BALANCE - 100, WithdrawReasons::all());
+		assert_noop!(
+			ConnectFour::commit_toss(Origin::signed(4), H256::repeat_byte(1)),
+			Error::<Test>::InsufficientStake
+		);
+	});
+}
+
+#[test]
+fn test_set_founder_key() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		assert_eq!(ConnectFour::founder_key(), Some(FOUNDER));
+		assert_noop!(
+			ConnectFour::set_founder_key(Origin::signed(FOUNDER), 3),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(ConnectFour::set_founder_key(Origin::root(), 3));
+		assert_eq!(ConnectFour::founder_key(), Some(3));
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::FounderKeyChanged(3)));
+
+		// The rake goes to the new founder key
+		let stake = 100;
+		let board_id = start_wager_game(PLAYER_1 as u64, PLAYER_2 as u64, stake);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(PLAYER_1 as u64)));
+		assert_eq!(Balances::free_balance(3), INITIAL_BALANCE + Rake::get() * (2 * stake));
+	});
+}
+
+#[test]
+fn test_timeout_slash() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		let board = ConnectFour::boards(board_id);
+		let (at_turn, waiting) = if board.next_player == PLAYER_1 {
+			(board.red, board.blue)
+		} else {
+			(board.blue, board.red)
+		};
+
+		// Root ends the turn of the stalling player
+		run_to_block(100 + 5);
+		assert_ok!(ConnectFour::force_end_turn(Origin::root(), board_id, board.last_turn));
+
+		let slash = CasualTimeoutSlash::get() * BoardDeposit::get();
+		let reward = TimeoutSlashReward::get() * slash;
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::TimeoutSlashed(board_id, at_turn, slash))
+		));
+		assert!(ConnectFour::boards(board_id).board_state == BoardState::Finished(waiting));
+		assert_eq!(ConnectFour::board_deposits(board_id, at_turn), BoardDeposit::get() - slash);
+		assert_eq!(ConnectFour::board_deposits(board_id, waiting), BoardDeposit::get());
+		assert_eq!(Balances::free_balance(waiting), INITIAL_BALANCE - BoardDeposit::get() + reward);
+		assert_eq!(Balances::free_balance(FOUNDER), INITIAL_BALANCE + slash - reward);
+
+		// The rest of the deposit is burnt on cleanup as the staller never placed a stone
+		run_to_block(100 + 5 + 20);
+		assert_eq!(Balances::reserved_balance(at_turn), 0);
+		assert_eq!(Balances::free_balance(at_turn), INITIAL_BALANCE - BoardDeposit::get());
+	});
+}
+
+#[test]
+fn test_market_settlement() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		let (red, blue) = (PLAYER_1 as u64, PLAYER_2 as u64);
+
+		assert_noop!(
+			ConnectFour::place_bet(Origin::signed(red), board_id, red, 100),
+			Error::<Test>::PlayerCannotBet
+		);
+		assert_noop!(
+			ConnectFour::place_bet(Origin::signed(3), board_id, 5, 100),
+			Error::<Test>::NotBoardPlayer
+		);
+		assert_noop!(
+			ConnectFour::place_bet(Origin::signed(3), board_id, red, MinBet::get() - 1),
+			Error::<Test>::BetTooSmall
+		);
+
+		assert_ok!(ConnectFour::place_bet(Origin::signed(3), board_id, red, 100));
+		assert_ok!(ConnectFour::place_bet(Origin::signed(4), board_id, red, 300));
+		assert_ok!(ConnectFour::place_bet(Origin::signed(5), board_id, blue, 200));
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::BetPlaced(board_id, 5, blue, 200)));
+		assert_eq!(Balances::reserved_balance(5), 200);
+		assert_noop!(
+			ConnectFour::place_bet(Origin::signed(6), board_id, blue, 100),
+			Error::<Test>::TooManyBettors
+		);
+
+		let market = ConnectFour::markets(board_id).unwrap();
+		assert_eq!((market.red_pool, market.blue_pool, market.bettors), (400, 200, 3));
+
+		// The backers of red share the pool of blue in proportion to their bets
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(red)));
+		assert_eq!(Balances::free_balance(3), INITIAL_BALANCE + 50);
+		assert_eq!(Balances::free_balance(4), INITIAL_BALANCE + 150);
+		assert_eq!(Balances::free_balance(5), INITIAL_BALANCE - 200);
+		assert_eq!(Balances::reserved_balance(3) + Balances::reserved_balance(4) + Balances::reserved_balance(5), 0);
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::BetPaid(board_id, 4, 450))
+		));
+		assert!(!Markets::<Test>::contains_key(board_id));
+		assert_eq!(ConnectFour::bets(board_id, 3), Bet::default());
+	});
+}
+
+#[test]
+fn test_market_refund_and_close() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		// A draw refunds all bets
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		assert_ok!(ConnectFour::place_bet(Origin::signed(3), board_id, PLAYER_1 as u64, 100));
+		assert_ok!(ConnectFour::place_bet(Origin::signed(4), board_id, PLAYER_2 as u64, 100));
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, None));
+		assert_eq!(Balances::free_balance(3), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(4), INITIAL_BALANCE);
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::BetsRefunded(board_id))
+		));
+
+		// A voided board refunds all bets
+		let board_id = start_game(5, 6);
+		assert_ok!(ConnectFour::place_bet(Origin::signed(3), board_id, 5, 100));
+		assert_ok!(ConnectFour::void_game(Origin::root(), board_id));
+		assert_eq!(Balances::free_balance(3), INITIAL_BALANCE);
+		assert_eq!(Balances::reserved_balance(3), 0);
+
+		// The market closes after the configured number of moves
+		run_to_block(200);
+		let board_id = start_game(PLAYER_1 as u64, PLAYER_2 as u64);
+		play_moves(board_id, &[0, 1, 2, 3][..MarketCloseMoves::get() as usize]);
+		assert_noop!(
+			ConnectFour::place_bet(Origin::signed(3), board_id, PLAYER_1 as u64, 100),
+			Error::<Test>::MarketClosed
+		);
+
+		// The market closes at the end of the market period
+		let board_id = start_game(5, 6);
+		run_to_block(200 + MarketPeriod::get());
+		assert_noop!(
+			ConnectFour::place_bet(Origin::signed(3), board_id, 5, 100),
+			Error::<Test>::MarketClosed
+		);
+	});
+}
+
+#[test]
+fn test_asset_wager() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		let (asset, stake) = (0, 100);
+		assert_ok!(Assets::force_create(Origin::root(), asset, 1, true, 1));
+		for who in 1..=4 {
+			assert_ok!(Assets::mint(Origin::signed(1), asset, who, INITIAL_BALANCE));
+		}
+
+		// Assets need a minimum stake set by root
+		assert_noop!(
+			ConnectFour::new_game(Origin::signed(1), 2, stake, Some(asset)),
+			Error::<Test>::AssetNotAllowed
+		);
+		assert_noop!(
+			ConnectFour::set_min_stake(Origin::signed(1), asset, Some(50)),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(ConnectFour::set_min_stake(Origin::root(), asset, Some(50)));
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::MinStakeSet(asset, Some(50))));
+		assert_noop!(
+			ConnectFour::new_game(Origin::signed(1), 2, 49, Some(asset)),
+			Error::<Test>::StakeTooSmall
+		);
+
+		// The stakes are escrowed and the winner receives the opponent's stake minus the rake
+		let board_id = start_asset_game(1, 2, stake, Some(asset));
+		assert_eq!(Assets::balance(asset, ConnectFour::escrow_account()), 2 * stake);
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE - BoardDeposit::get());
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(1)));
+		let rake = Rake::get() * (2 * stake);
+		assert_eq!(Assets::balance(asset, ConnectFour::escrow_account()), 0);
+		assert_eq!(Assets::balance(asset, 1), INITIAL_BALANCE + stake - rake);
+		assert_eq!(Assets::balance(asset, 2), INITIAL_BALANCE - stake);
+		assert_eq!(Assets::balance(asset, FOUNDER), rake);
+
+		// A voided board returns the escrowed stakes
+		let board_id = start_asset_game(3, 4, stake, Some(asset));
+		assert_ok!(ConnectFour::void_game(Origin::root(), board_id));
+		assert_eq!(Assets::balance(asset, 3), INITIAL_BALANCE);
+		assert_eq!(Assets::balance(asset, 4), INITIAL_BALANCE);
+
+		// Disallowed assets can't be wagered in anymore
+		assert_ok!(ConnectFour::set_min_stake(Origin::root(), asset, None));
+		assert_noop!(
+			ConnectFour::new_game(Origin::signed(3), 4, stake, Some(asset)),
+			Error::<Test>::AssetNotAllowed
+		);
+	});
+}
+
+#[test]
+fn test_rewards() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		// The winner of a long enough board gets rewarded
+		let board_id = start_game(1, 2);
+		play_moves(board_id, &[0, 1, 0, 1]);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(1)));
+		assert_eq!(Balances::total_balance(&1), INITIAL_BALANCE + WinReward::get());
+		assert_eq!(Balances::total_balance(&2), INITIAL_BALANCE);
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::RewardPaid(board_id, 1, WinReward::get()))
+		));
+
+		// Short boards aren't rewarded
+		let board_id = start_game(3, 4);
+		play_moves(board_id, &[0, 1, 0]);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(3)));
+		assert_eq!(Balances::total_balance(&3), INITIAL_BALANCE);
+
+		// Winning against a recent opponent again isn't rewarded
+		run_to_block(130);
+		let board_id = start_game(1, 2);
+		play_moves(board_id, &[0, 1, 0, 1]);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(1)));
+		assert_eq!(Balances::total_balance(&1), INITIAL_BALANCE + WinReward::get());
+
+		// Draws reward both players, the era emission is capped
+		let board_id = start_game(3, 4);
+		play_moves(board_id, &[0, 1, 0, 1]);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, None));
+		assert_eq!(Balances::total_balance(&3), INITIAL_BALANCE + DrawReward::get());
+		assert_eq!(Balances::total_balance(&4), INITIAL_BALANCE + DrawReward::get());
+
+		let board_id = start_game(5, 6);
+		play_moves(board_id, &[0, 1, 0, 1]);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(5)));
+		let emitted = WinReward::get() + 2 * DrawReward::get();
+		assert_eq!(Balances::total_balance(&5), INITIAL_BALANCE + MaxEraEmission::get() - emitted);
+		assert_eq!(ConnectFour::era_emission(), (1, MaxEraEmission::get()));
+
+		// The next era starts without emission
+		run_to_block(200);
+		let board_id = start_game(1, 3);
+		play_moves(board_id, &[0, 1, 0, 1]);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(3)));
+		assert_eq!(Balances::total_balance(&3), INITIAL_BALANCE + DrawReward::get() + WinReward::get());
+		assert_eq!(ConnectFour::era_emission(), (2, WinReward::get()));
+	});
+}
+
+#[test]
+fn test_sponsored_fees() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		type Fees = <Test as pallet_transaction_payment::Config>::OnChargeTransaction;
+		let info = DispatchInfo::default();
+		let charge = |who: u64, call: mock::Call, fee: u64, corrected_fee: u64| {
+			let liquidity = Fees::withdraw_fee(&who, &call, &info, fee, 0).unwrap();
+			assert_ok!(Fees::correct_and_deposit_fee(&who, &info, &Default::default(), corrected_fee, 0, liquidity));
+		};
+		let (sponsor, budget) = (6, 100);
+
+		assert_noop!(
+			ConnectFour::sponsor_player(Origin::signed(sponsor), 1),
+			Error::<Test>::NoSponsorship
+		);
+		assert_noop!(
+			ConnectFour::fund_sponsorship(Origin::signed(sponsor), budget, 100),
+			Error::<Test>::SponsorshipExpired
+		);
+		assert_noop!(
+			ConnectFour::fund_sponsorship(Origin::signed(sponsor), INITIAL_BALANCE + 1, 150),
+			Error::<Test>::InsufficientBudget
+		);
+		assert_ok!(ConnectFour::fund_sponsorship(Origin::signed(sponsor), budget, 150));
+		assert_eq!(Balances::reserved_balance(sponsor), budget);
+		assert_ok!(ConnectFour::sponsor_player(Origin::signed(sponsor), 1));
+		assert_eq!(last_event(), mock::Event::pallet_connectfour(crate::Event::PlayerSponsored(sponsor, 1)));
+
+		// Other sponsors can't take over a sponsored player
+		assert_ok!(ConnectFour::fund_sponsorship(Origin::signed(5), budget, 150));
+		assert_noop!(
+			ConnectFour::sponsor_player(Origin::signed(5), 1),
+			Error::<Test>::AlreadySponsored
+		);
+
+		// The sponsor pays the fee of the sponsored player and gets the refund back into the budget
+		charge(1, mock::Call::ConnectFour(crate::Call::new_game(2, 0, None)), 10, 4);
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE);
+		assert_eq!(Balances::total_balance(&sponsor), INITIAL_BALANCE - 4);
+		assert_eq!(Balances::reserved_balance(sponsor), budget - 4);
+		assert_eq!(ConnectFour::sponsorships(sponsor).unwrap().budget, budget - 4);
+
+		// Other calls and players pay their own fees
+		charge(1, mock::Call::ConnectFour(crate::Call::do_something(1)), 10, 10);
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE - 10);
+		charge(2, mock::Call::ConnectFour(crate::Call::new_game(1, 0, None)), 10, 10);
+		assert_eq!(Balances::free_balance(2), INITIAL_BALANCE - 10);
+
+		// Moves on a sponsored board are paid by the board's sponsor
+		let board_id = start_game(3, 4);
+		assert_ok!(ConnectFour::sponsor_board(Origin::signed(5), board_id));
+		charge(4, mock::Call::ConnectFour(crate::Call::play_turn(0)), 10, 10);
+		assert_eq!(Balances::reserved_balance(5), budget - 10);
+		assert_eq!(Balances::free_balance(4), INITIAL_BALANCE - BoardDeposit::get());
+
+		// Fees exceeding the budget or after the expiry are paid by the player
+		charge(4, mock::Call::ConnectFour(crate::Call::play_turn(0)), budget, budget);
+		assert_eq!(Balances::free_balance(4), INITIAL_BALANCE - BoardDeposit::get() - budget);
+		run_to_block(151);
+		charge(1, mock::Call::ConnectFour(crate::Call::new_game(2, 0, None)), 10, 10);
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE - 20);
+
+		// Closing returns the rest of the budget
+		assert_ok!(ConnectFour::unsponsor_player(Origin::signed(sponsor), 1));
+		assert_noop!(
+			ConnectFour::unsponsor_player(Origin::signed(sponsor), 1),
+			Error::<Test>::NotSponsor
+		);
+		assert_ok!(ConnectFour::close_sponsorship(Origin::signed(sponsor)));
+		assert_eq!(Balances::reserved_balance(sponsor), 0);
+		assert_eq!(Balances::free_balance(sponsor), INITIAL_BALANCE - 4);
+		assert_noop!(
+			ConnectFour::close_sponsorship(Origin::signed(sponsor)),
+			Error::<Test>::NoSponsorship
+		);
+	});
+}
+
+#[test]
+fn test_ratings() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		assert_eq!(crate::elo::Elo::expected(1500, 1500), 500);
+		assert_eq!(crate::elo::Elo::expected(1900, 1500), 909);
+		assert_eq!(crate::elo::Elo::expected(1500, 2500), 10);
+		assert_eq!(crate::elo::Elo::apply(10, -20), 0);
+
+		// New players start provisional and move fast
+		assert_eq!(ConnectFour::ratings(1), Rating { rating: 1500, games: 0, season: 0 });
+		let board_id = start_game(1, 2);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(1)));
+		assert_eq!(ConnectFour::ratings(1), Rating { rating: 1520, games: 1, season: 0 });
+		assert_eq!(ConnectFour::ratings(2), Rating { rating: 1480, games: 1, season: 0 });
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::GameFinished(board_id, Some(1), 20, -20))
+		));
+
+		// A draw moves the higher rated player down
+		let board_id = start_game(3, 4);
+		Ratings::<Test>::insert(3, Rating { rating: 1520, games: 1, season: 0 });
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, None));
+		assert_eq!(ConnectFour::ratings(3).rating, 1519);
+		assert_eq!(ConnectFour::ratings(4).rating, 1501);
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::GameFinished(board_id, None, -1, 1))
+		));
+
+		// Established players move slower, timeouts are rated as well
+		let board_id = start_game(5, 6);
+		Ratings::<Test>::insert(5, Rating { rating: 1500, games: 30, season: 0 });
+		let board = ConnectFour::boards(board_id);
+		assert_ok!(ConnectFour::force_end_turn(Origin::root(), board_id, board.last_turn));
+		let (winner, loser) = if board.next_player == PLAYER_1 { (6, 5) } else { (5, 6) };
+		assert!(ConnectFour::boards(board_id).board_state == BoardState::Finished(winner));
+		if winner == 5 {
+			assert_eq!(ConnectFour::ratings(winner).rating, 1510);
+			assert_eq!(ConnectFour::ratings(loser).rating, 1480);
+		} else {
+			assert_eq!(ConnectFour::ratings(winner).rating, 1520);
+			assert_eq!(ConnectFour::ratings(loser).rating, 1490);
+		}
+
+		// Forced boards return the whole deposits on cleanup
+		run_to_block(100 + 20);
+		for player in 1..=4 {
+			assert_eq!(Balances::reserved_balance(player), 0);
+			assert_eq!(Balances::free_balance(player), INITIAL_BALANCE);
+		}
+	});
+}
+
+#[test]
+fn test_player_stats() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		// The starting player wins by connecting four
+		let board_id = start_game(1, 2);
+		let board = ConnectFour::boards(board_id);
+		let (first, second) = if board.next_player == PLAYER_1 { (1, 2) } else { (2, 1) };
+		play_moves(board_id, &[0, 1, 0, 1, 0, 1, 0]);
+		assert!(ConnectFour::boards(board_id).board_state == BoardState::Finished(first));
+
+		let stats = ConnectFour::player_stats(first);
+		assert_eq!((stats.wins, stats.streak, stats.best_streak, stats.first_player_games), (1, 1, 1, 1));
+		assert_eq!((stats.games, stats.moves), (1, 7));
+		let stats = ConnectFour::player_stats(second);
+		assert_eq!((stats.losses, stats.streak, stats.first_player_games), (1, 0, 0));
+
+		// Resigning hands the win to the opponent and ends the streak
+		assert_noop!(ConnectFour::resign(Origin::signed(5)), Error::<Test>::NoPlayerBoard);
+		run_to_block(130);
+		let board_id = start_game(1, 2);
+		play_moves(board_id, &[3]);
+		assert_ok!(ConnectFour::resign(Origin::signed(first)));
+		assert!(ConnectFour::boards(board_id).board_state == BoardState::Finished(second));
+		assert_noop!(
+			ConnectFour::resign(Origin::signed(first)),
+			"Board is not running, check if already finished."
+		);
+
+		let stats = ConnectFour::player_stats(first);
+		assert_eq!((stats.wins, stats.losses, stats.resignations, stats.streak, stats.best_streak), (1, 1, 1, 0, 1));
+		assert_eq!(ConnectFour::average_moves(&first), 4);
+		let stats = ConnectFour::player_stats(second);
+		assert_eq!((stats.wins, stats.losses, stats.resignations, stats.streak), (1, 1, 0, 1));
+
+		// The opponent of a resigning player keeps the deposit without placing a stone
+		run_to_block(130 + 20);
+		assert_eq!(Balances::reserved_balance(second), 0);
+		assert_eq!(Balances::free_balance(second), INITIAL_BALANCE);
+
+		// Timeouts are counted for both sides
+		let board_id = start_game(3, 4);
+		let board = ConnectFour::boards(board_id);
+		let (at_turn, waiting) = if board.next_player == PLAYER_1 { (3, 4) } else { (4, 3) };
+		assert_ok!(ConnectFour::force_end_turn(Origin::root(), board_id, board.last_turn));
+		assert_eq!(ConnectFour::player_stats(at_turn).timeouts_caused, 1);
+		assert_eq!(ConnectFour::player_stats(waiting).timeouts_suffered, 1);
+		assert_eq!(ConnectFour::player_stats(waiting).timeouts_caused, 0);
+
+		// Draws count for both players
+		let board_id = start_game(5, 6);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, None));
+		assert_eq!(ConnectFour::player_stats(5).draws, 1);
+		assert_eq!(ConnectFour::player_stats(6).draws, 1);
+	});
+}
+
+#[test]
+fn test_leaderboard_and_seasons() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		// Players reaching a rating go below the ones already there, the last place drops out
+		for (winner, loser) in [(1, 2), (3, 4), (5, 6)].iter() {
+			let board_id = start_game(*winner, *loser);
+			assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(*winner)));
+		}
+		let standings = vec![(1, 1520), (3, 1520), (5, 1520)];
+		assert_eq!(ConnectFour::leaderboard().to_vec(), standings);
+
+		// The standings get archived at the end of the season
+		run_to_block(SeasonLength::get());
+		assert_eq!(ConnectFour::current_season(), 1);
+		assert_eq!(ConnectFour::season_standings(0).unwrap().to_vec(), standings);
+		assert!(ConnectFour::leaderboard().is_empty());
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::SeasonEnded(0))
+		));
+
+		// Nobody stalled, so the cleanup returned all deposits
+		for player in 1..=6 {
+			assert_eq!(Balances::reserved_balance(player), 0);
+			assert_eq!(Balances::free_balance(player), INITIAL_BALANCE);
+		}
+
+		// Ratings get soft reset towards the initial rating
+		assert_eq!(ConnectFour::season_rating(&1), Rating { rating: 1510, games: 1, season: 1 });
+		assert_eq!(ConnectFour::season_rating(&2), Rating { rating: 1490, games: 1, season: 1 });
+		let board_id = start_game(1, 2);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(1)));
+		assert_eq!(ConnectFour::ratings(1), Rating { rating: 1529, games: 2, season: 1 });
+		assert_eq!(ConnectFour::leaderboard().to_vec(), vec![(1, 1529), (2, 1471)]);
+	});
+}
+
+#[test]
+fn test_matchmaking_queue() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(1);
+
+		let casual = GameSettings { mode: GameMode::Casual, turn_blocks: 5 };
+		assert_noop!(
+			ConnectFour::queue(Origin::signed(1), GameSettings { mode: GameMode::Casual, turn_blocks: 2 }),
+			Error::<Test>::InvalidTimeControl
+		);
+
+		// A single player waits for an opponent
+		assert_ok!(ConnectFour::queue(Origin::signed(1), casual));
+		assert_noop!(ConnectFour::queue(Origin::signed(1), casual), Error::<Test>::AlreadyQueued);
+
+		// Leaving the queue
+		assert_ok!(ConnectFour::dequeue(Origin::signed(1)));
+		assert_noop!(ConnectFour::dequeue(Origin::signed(1)), Error::<Test>::NotQueued);
+		assert!(!MatchQueues::<Test>::contains_key(casual));
+
+		// Players get paired once their windows widened to the rating difference
+		Ratings::<Test>::insert(2, Rating { rating: 1600, games: 40, season: 0 });
+		assert_ok!(ConnectFour::queue(Origin::signed(1), casual));
+		assert_ok!(ConnectFour::queue(Origin::signed(2), casual));
+		run_to_block(5);
+		assert!(!PlayerBoard::<Test>::contains_key(1));
+		run_to_block(6);
+		let board_id = ConnectFour::player_board(1);
+		assert_eq!(ConnectFour::player_board(2), board_id);
+		assert_eq!(ConnectFour::board_settings(board_id), casual);
+		assert_eq!(ConnectFour::board_schedules(board_id), Some(11));
+		assert_eq!(Balances::reserved_balance(1), BoardDeposit::get());
+		assert!(!QueuedPlayers::<Test>::contains_key(1));
+		assert!(!MatchQueues::<Test>::contains_key(casual));
+
+		// Players with an open board can't queue, casual boards don't change ratings
+		assert_noop!(ConnectFour::queue(Origin::signed(1), casual), Error::<Test>::PlayerBoardExists);
+		assert_ok!(ConnectFour::force_finish(Origin::root(), board_id, Some(2)));
+		assert_eq!(ConnectFour::ratings(1), Rating::default());
+
+		// Queues are bounded and a block only starts a limited number of boards
+		let ranked = GameSettings::default();
+		for player in 3..=5 {
+			assert_ok!(ConnectFour::queue(Origin::signed(player), ranked));
+		}
+		assert_noop!(ConnectFour::queue(Origin::signed(6), ranked), Error::<Test>::QueueFull);
+		run_to_block(7);
+		assert_eq!(ConnectFour::player_board(3), ConnectFour::player_board(4));
+		assert_eq!(ConnectFour::queued_players(5), Some(ranked));
+
+		// Starting a board leaves the queue
+		assert_ok!(ConnectFour::new_game(Origin::signed(6), 5, 0, None));
+		assert_eq!(ConnectFour::queued_players(5), None);
+		assert!(!MatchQueues::<Test>::contains_key(ranked));
+
+		// The scan stops once the comparisons of a block are used up and resumes in the next one
+		Ratings::<Test>::insert(8, Rating { rating: 2500, games: 40, season: 0 });
+		Ratings::<Test>::insert(10, Rating { rating: 3500, games: 40, season: 0 });
+		for player in [7, 8, 10].iter() {
+			Balances::make_free_balance_be(player, INITIAL_BALANCE);
+			assert_ok!(ConnectFour::queue(Origin::signed(*player), casual));
+		}
+		run_to_block(8);
+		assert_eq!(ConnectFour::queue_cursor(), (10, 1, 2));
+		run_to_block(9);
+		assert_eq!(ConnectFour::queue_cursor(), (10, 0, 0));
+		assert_eq!(ConnectFour::match_queues(casual).len(), 3);
+	});
+}
+
+#[test]
+fn test_challenge_lobby() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(1);
+
+		let casual = GameSettings { mode: GameMode::Casual, turn_blocks: 5 };
+		let ranked = GameSettings::default();
+
+		// Challenges need a valid rating range and expiry
+		assert_noop!(
+			ConnectFour::post_challenge(Origin::signed(1), casual, 100, None, (1600, 1400), false, 5),
+			Error::<Test>::InvalidRatingRange
+		);
+		assert_noop!(
+			ConnectFour::post_challenge(Origin::signed(1), casual, 100, None, (1400, 1600), false, 1),
+			Error::<Test>::ChallengeExpired
+		);
+
+		// One open challenge per creator in a bounded lobby
+		assert_ok!(ConnectFour::post_challenge(Origin::signed(1), casual, 100, None, (1400, 1600), false, 5));
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::ChallengePosted(1))
+		));
+		assert_noop!(
+			ConnectFour::post_challenge(Origin::signed(1), ranked, 0, None, (0, u32::MAX), false, 5),
+			Error::<Test>::ChallengeExists
+		);
+		assert_ok!(ConnectFour::post_challenge(Origin::signed(2), ranked, 0, None, (0, u32::MAX), false, 5));
+		assert_noop!(
+			ConnectFour::post_challenge(Origin::signed(3), ranked, 0, None, (0, u32::MAX), false, 5),
+			Error::<Test>::LobbyFull
+		);
+
+		// Cancelling frees the slot
+		assert_ok!(ConnectFour::cancel_challenge(Origin::signed(2)));
+		assert_noop!(ConnectFour::cancel_challenge(Origin::signed(2)), Error::<Test>::NoChallenge);
+		assert_eq!(ConnectFour::lobby().len(), 1);
+
+		// Only accounts within the rating range can accept
+		assert_noop!(ConnectFour::accept_challenge(Origin::signed(1), 1), Error::<Test>::NoFakePlay);
+		assert_noop!(ConnectFour::accept_challenge(Origin::signed(3), 2), Error::<Test>::NoChallenge);
+		Ratings::<Test>::insert(4, Rating { rating: 1700, games: 40, season: 0 });
+		assert_noop!(ConnectFour::accept_challenge(Origin::signed(4), 1), Error::<Test>::RatingOutOfRange);
+
+		// The first eligible account starts the board with the challenge parameters
+		assert_ok!(ConnectFour::accept_challenge(Origin::signed(3), 1));
+		let board_id = ConnectFour::player_board(1);
+		assert_eq!(ConnectFour::player_board(3), board_id);
+		assert_eq!(ConnectFour::board_settings(board_id), casual);
+		assert_eq!(ConnectFour::wagers(board_id).unwrap().stake, 100);
+		assert!(ConnectFour::lobby().is_empty());
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::ChallengeAccepted(1, 3, board_id))
+		));
+
+		// Challenges nobody accepted expire
+		assert_ok!(ConnectFour::post_challenge(Origin::signed(4), ranked, 0, None, (0, u32::MAX), false, 3));
+		run_to_block(3);
+		assert!(ConnectFour::lobby().is_empty());
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::ChallengeExpired(4))
+		));
+	});
+}
+
+#[test]
+fn test_penalty_box() {
+	new_test_ext().execute_with(|| {
+
+		// Create a board and let it time out before the reveals, both players stall.
+		fn stall(red: u64, blue: u64) {
+			assert_ok!(ConnectFour::new_game(Origin::signed(red), blue, 0, None));
+			assert_ok!(ConnectFour::commit_toss(Origin::signed(red), H256::repeat_byte(1)));
+			assert_ok!(ConnectFour::commit_toss(Origin::signed(blue), H256::repeat_byte(2)));
+			let board = ConnectFour::boards(ConnectFour::player_board(red));
+			assert_ok!(ConnectFour::force_end_turn(Origin::root(), board.id, board.last_turn));
+		}
+
+		run_to_block(100);
+
+		// Timeouts below the threshold are only counted
+		stall(1, 2);
+		assert_eq!(ConnectFour::penalty_box(1).timeouts, 1);
+		assert_eq!(ConnectFour::penalty_box(1).banned_until, 0);
+
+		// Reaching the threshold bans the player from starting boards
+		run_to_block(120);
+		stall(1, 2);
+		assert_eq!(ConnectFour::penalty_box(1).banned_until, 120 + BanLength::get());
+		assert!(System::events().iter().any(|record|
+			record.event == mock::Event::pallet_connectfour(crate::Event::PlayerBanned(1, 130))
+		));
+		assert_noop!(ConnectFour::new_game(Origin::signed(3), 1, 0, None), Error::<Test>::PlayerBanned);
+		assert_noop!(ConnectFour::queue(Origin::signed(1), GameSettings::default()), Error::<Test>::PlayerBanned);
+		assert_noop!(
+			ConnectFour::post_challenge(Origin::signed(1), GameSettings::default(), 0, None, (0, u32::MAX), false, 200),
+			Error::<Test>::PlayerBanned
+		);
+
+		// Every further ban lasts twice as long
+		run_to_block(140);
+		stall(1, 2);
+		run_to_block(160);
+		stall(1, 2);
+		assert_eq!(ConnectFour::penalty_box(1).bans, 2);
+		assert_eq!(ConnectFour::penalty_box(1).banned_until, 160 + 2 * BanLength::get());
+
+		// Timeouts outside of the window don't count anymore
+		stall(3, 4);
+		run_to_block(300);
+		stall(3, 4);
+		assert_eq!(ConnectFour::penalty_box(3).timeouts, 1);
+		assert_eq!(ConnectFour::penalty_box(3).bans, 0);
+
+		// Forfeiting the coin toss before both players committed isn't counted
+		assert_ok!(ConnectFour::new_game(Origin::signed(5), 6, 0, None));
+		let board_id = ConnectFour::player_board(5);
+		assert_ok!(ConnectFour::commit_toss(Origin::signed(6), H256::repeat_byte(2)));
+		let board = ConnectFour::boards(board_id);
+		assert_ok!(ConnectFour::force_end_turn(Origin::root(), board_id, board.last_turn));
+		assert!(ConnectFour::boards(board_id).board_state == BoardState::Finished(6));
+		assert_eq!(ConnectFour::penalty_box(5).timeouts, 0);
+	});
+}
+
+#[test]
+fn test_block_and_friends() {
+	new_test_ext().execute_with(|| {
+
+		run_to_block(100);
+
+		// Blocked accounts can't challenge the player
+		assert_noop!(ConnectFour::block_account(Origin::signed(1), 1), Error::<Test>::NoFakePlay);
+		assert_ok!(ConnectFour::block_account(Origin::signed(1), 2));
+		assert_noop!(ConnectFour::block_account(Origin::signed(1), 2), Error::<Test>::AlreadyBlocked);
+		assert_noop!(ConnectFour::new_game(Origin::signed(2), 1, 0, None), Error::<Test>::BlockedByOpponent);
+
+		// Friends lists are bounded and don't take blocked accounts
+		assert_noop!(ConnectFour::add_friend(Origin::signed(1), 2), Error::<Test>::AlreadyBlocked);
+		assert_ok!(ConnectFour::add_friend(Origin::signed(1), 3));
+		assert_noop!(ConnectFour::add_friend(Origin::signed(1), 3), Error::<Test>::AlreadyFriend);
+		assert_ok!(ConnectFour::add_friend(Origin::signed(1), 4));
+		assert_noop!(ConnectFour::add_friend(Origin::signed(1), 5), Error::<Test>::TooManyFriends);
+
+		// Blocking a friend removes them from the friends list
+		assert_ok!(ConnectFour::block_account(Origin::signed(1), 4));
+		assert_eq!(ConnectFour::friends(1).to_vec(), vec![3]);
+
+		// Friend-only challenges can only be accepted by friends
+		let ranked = GameSettings::default();
+		assert_ok!(ConnectFour::post_challenge(Origin::signed(1), ranked, 0, None, (0, u32::MAX), true, 200));
+		assert_noop!(ConnectFour::accept_challenge(Origin::signed(2), 1), Error::<Test>::BlockedByOpponent);
+		assert_noop!(ConnectFour::accept_challenge(Origin::signed(6), 1), Error::<Test>::NotFriend);
+		assert_ok!(ConnectFour::accept_challenge(Origin::signed(3), 1));
+		assert_eq!(ConnectFour::player_board(1), ConnectFour::player_board(3));
+
+		// Queued players who blocked each other don't get paired
+		assert_ok!(ConnectFour::block_account(Origin::signed(5), 6));
+		assert_ok!(ConnectFour::queue(Origin::signed(5), ranked));
+		assert_ok!(ConnectFour::queue(Origin::signed(6), ranked));
+		run_to_block(101);
+		assert_eq!(ConnectFour::queued_players(5), Some(ranked));
+		assert_ok!(ConnectFour::unblock_account(Origin::signed(5), 6));
+		assert_noop!(ConnectFour::unblock_account(Origin::signed(5), 6), Error::<Test>::NotBlocked);
+		run_to_block(102);
+		assert_eq!(ConnectFour::player_board(5), ConnectFour::player_board(6));
+
+		// Removing friends
+		assert_ok!(ConnectFour::remove_friend(Origin::signed(1), 3));
+		assert!(!Friends::<Test>::contains_key(1));
+		assert_noop!(ConnectFour::remove_friend(Origin::signed(1), 3), Error::<Test>::NotFriend);
+	});
+}
@@ -7,9 +7,10 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_std::prelude::*;
+use codec::{Encode, Decode};
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
-	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
+	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature, RuntimeDebug,
 	transaction_validity::{TransactionValidity, TransactionSource},
 };
 use sp_runtime::traits::{
@@ -30,16 +31,13 @@ pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
 pub use sp_runtime::{Permill, Perbill};
 pub use frame_support::{
-	construct_runtime, parameter_types, StorageValue,
-	traits::{KeyOwnerProofSystem, Randomness},
+	construct_runtime, parameter_types, StorageValue, PalletId,
+	traits::{KeyOwnerProofSystem, Randomness, InstanceFilter},
 	weights::{
 		Weight, IdentityFee,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 	},
 };
-use frame_system::{
-	EnsureRoot
-};
 use pallet_transaction_payment::CurrencyAdapter;
 
 /// Import the connectfour pallet.
@@ -261,7 +259,7 @@ parameter_types! {
 }
 
 impl pallet_transaction_payment::Config for Runtime {
-	type OnChargeTransaction = CurrencyAdapter<Balances, ()>;
+	type OnChargeTransaction = pallet_connectfour::SponsoredFees<Runtime, CurrencyAdapter<Balances, ()>>;
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = IdentityFee<Balance>;
 	type FeeMultiplierUpdate = ();
@@ -273,34 +271,169 @@ impl pallet_sudo::Config for Runtime {
 }
 
 parameter_types! {
-	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) *
-		BlockWeights::get().max_block;
-	pub const MaxScheduledPerBlock: u32 = 50;
+	pub const AssetDeposit: Balance = 100_000_000_000_000;
+	pub const ApprovalDeposit: Balance = 1_000_000_000_000;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: Balance = 10_000_000_000_000;
+	pub const MetadataDepositPerByte: Balance = 1_000_000_000_000;
 }
 
-impl pallet_scheduler::Config for Runtime {
+impl pallet_assets::Config for Runtime {
 	type Event = Event;
-	type Origin = Origin;
-	type PalletsOrigin = OriginCaller;
-	type Call = Call;
-	type MaximumWeight = MaximumSchedulerWeight;
-	type ScheduleOrigin = EnsureRoot<AccountId>;
-	type MaxScheduledPerBlock = MaxScheduledPerBlock;
-	type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
+	type Balance = Balance;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = StringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const ProxyDepositBase: Balance = 1_000_000_000_000;
+	pub const ProxyDepositFactor: Balance = 100_000_000_000;
+	pub const MaxProxies: u16 = 32;
+	pub const AnnouncementDepositBase: Balance = 1_000_000_000_000;
+	pub const AnnouncementDepositFactor: Balance = 200_000_000_000;
+	pub const MaxPending: u16 = 32;
+}
+
+/// The type used to represent the kinds of proxying allowed.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub enum ProxyType {
+	/// Allow any call.
+	Any,
+	/// Allow only the player calls of the connect four game, to let bots play for an account.
+	ConnectFour,
+}
+
+impl Default for ProxyType { fn default() -> Self { Self::Any } }
+
+impl InstanceFilter<Call> for ProxyType {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			ProxyType::Any => true,
+			ProxyType::ConnectFour => matches!(c,
+				Call::ConnectFour(pallet_connectfour::Call::new_game(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::play_turn(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::resign(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::claim_timeout(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::commit_toss(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::reveal_toss(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::authorize_move_key(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::revoke_move_key(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::queue(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::dequeue(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::post_challenge(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::cancel_challenge(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::accept_challenge(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::block_account(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::unblock_account(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::add_friend(..)) |
+				Call::ConnectFour(pallet_connectfour::Call::remove_friend(..))
+			),
+		}
+	}
+
+	fn is_superset(&self, o: &Self) -> bool {
+		match (self, o) {
+			(x, y) if x == y => true,
+			(ProxyType::Any, _) => true,
+			(_, ProxyType::Any) => false,
+			_ => false,
+		}
+	}
 }
 
-/// Used for test_module
-impl pallet_matchmaker::Config for Runtime {
+impl pallet_proxy::Config for Runtime {
 	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type ProxyType = ProxyType;
+	type ProxyDepositBase = ProxyDepositBase;
+	type ProxyDepositFactor = ProxyDepositFactor;
+	type MaxProxies = MaxProxies;
+	type WeightInfo = pallet_proxy::weights::SubstrateWeight<Runtime>;
+	type MaxPending = MaxPending;
+	type CallHasher = BlakeTwo256;
+	type AnnouncementDepositBase = AnnouncementDepositBase;
+	type AnnouncementDepositFactor = AnnouncementDepositFactor;
+}
+
+parameter_types! {
+	pub const ConnectFourPalletId: PalletId = PalletId(*b"py/conn4");
+	pub const BoardDeposit: Balance = 1_000_000_000_000;
+	pub const MaxDeadlinesPerBlock: u32 = 50;
+	pub const Rake: Perbill = Perbill::from_percent(2);
+	pub const CasualTimeoutSlash: Perbill = Perbill::from_percent(20);
+	pub const WageredTimeoutSlash: Perbill = Perbill::from_percent(50);
+	pub const TimeoutSlashReward: Perbill = Perbill::from_percent(50);
+	pub const MinBet: Balance = 100_000_000_000;
+	pub const MaxBettorsPerBoard: u32 = 100;
+	pub const MarketPeriod: BlockNumber = 10 * MINUTES;
+	pub const MarketCloseMoves: u8 = 10;
+	pub const WinReward: Balance = 1_000_000_000_000;
+	pub const DrawReward: Balance = 250_000_000_000;
+	pub const MinRewardMoves: u8 = 8;
+	pub const RewardOpponentWindow: u32 = 5;
+	pub const RewardEraLength: BlockNumber = DAYS;
+	pub const MaxEraEmission: Balance = 1_000_000_000_000_000;
+	pub const LeaderboardSize: u32 = 100;
+	pub const SeasonLength: BlockNumber = 28 * DAYS;
+	pub const SeasonResetFactor: Perbill = Perbill::from_percent(50);
+	pub const MatchWindow: u32 = 50;
+	pub const MatchWindowStep: u32 = 5;
+	pub const MaxQueueSize: u32 = 100;
+	pub const MaxMatchesPerBlock: u32 = 10;
+	pub const MaxQueueComparisons: u32 = 500;
+	pub const MaxOpenChallenges: u32 = 100;
+	pub const TimeoutWindow: BlockNumber = DAYS;
+	pub const TimeoutThreshold: u32 = 3;
+	pub const BanLength: BlockNumber = HOURS;
+	pub const MaxFriends: u32 = 100;
 }
 
 /// Configure the pallet-connectfour in pallets/connectfour.
 impl pallet_connectfour::Config for Runtime {
-	type Proposal = Call;
 	type Event = Event;
-	type Randomness = RandomnessCollectiveFlip;
-	type Scheduler = Scheduler;
-	type PalletsOrigin = OriginCaller;
+	type Currency = Balances;
+	type AssetId = u32;
+	type Assets = Assets;
+	type PalletId = ConnectFourPalletId;
+	type BoardDeposit = BoardDeposit;
+	type MaxDeadlinesPerBlock = MaxDeadlinesPerBlock;
+	type Rake = Rake;
+	type CasualTimeoutSlash = CasualTimeoutSlash;
+	type WageredTimeoutSlash = WageredTimeoutSlash;
+	type TimeoutSlashReward = TimeoutSlashReward;
+	type MinBet = MinBet;
+	type MaxBettorsPerBoard = MaxBettorsPerBoard;
+	type MarketPeriod = MarketPeriod;
+	type MarketCloseMoves = MarketCloseMoves;
+	type WinReward = WinReward;
+	type DrawReward = DrawReward;
+	type MinRewardMoves = MinRewardMoves;
+	type RewardOpponentWindow = RewardOpponentWindow;
+	type RewardEraLength = RewardEraLength;
+	type MaxEraEmission = MaxEraEmission;
+	type LeaderboardSize = LeaderboardSize;
+	type SeasonLength = SeasonLength;
+	type SeasonResetFactor = SeasonResetFactor;
+	type MatchWindow = MatchWindow;
+	type MatchWindowStep = MatchWindowStep;
+	type MaxQueueSize = MaxQueueSize;
+	type MaxMatchesPerBlock = MaxMatchesPerBlock;
+	type MaxQueueComparisons = MaxQueueComparisons;
+	type MaxOpenChallenges = MaxOpenChallenges;
+	type TimeoutWindow = TimeoutWindow;
+	type TimeoutThreshold = TimeoutThreshold;
+	type BanLength = BanLength;
+	type MaxFriends = MaxFriends;
 	//type WeightInfo = pallet_connectfour::weights::SubstrateWeight<Runtime>;
 }
 
@@ -319,10 +452,8 @@ construct_runtime!(
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
 		Sudo: pallet_sudo::{Pallet, Call, Config<T>, Storage, Event<T>},
-		Scheduler: pallet_scheduler::{Pallet, Call, Storage, Event<T>},
-
-		// Jeton Network Match Maker
-		MatchMaker: pallet_matchmaker::{Pallet, Call, Storage, Event<T>},
+		Proxy: pallet_proxy::{Pallet, Call, Storage, Event<T>},
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
 
 		// Connect Four Game
 		ConnectFour: pallet_connectfour::{Pallet, Call, Storage, Event<T>, Config<T>},
@@ -347,7 +478,8 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>
+	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_connectfour::CheckMove<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -528,3 +660,56 @@ impl_runtime_apis! {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::traits::StaticLookup;
+
+	#[test]
+	fn connect_four_proxy_filters_player_calls() {
+		let player_calls = vec![
+			Call::ConnectFour(pallet_connectfour::Call::new_game(Default::default(), 0, None)),
+			Call::ConnectFour(pallet_connectfour::Call::play_turn(0)),
+			Call::ConnectFour(pallet_connectfour::Call::resign()),
+			Call::ConnectFour(pallet_connectfour::Call::claim_timeout(Default::default(), 0)),
+			Call::ConnectFour(pallet_connectfour::Call::commit_toss(Default::default())),
+			Call::ConnectFour(pallet_connectfour::Call::reveal_toss(Default::default())),
+			Call::ConnectFour(pallet_connectfour::Call::authorize_move_key(Default::default(), None)),
+			Call::ConnectFour(pallet_connectfour::Call::revoke_move_key()),
+			Call::ConnectFour(pallet_connectfour::Call::queue(Default::default())),
+			Call::ConnectFour(pallet_connectfour::Call::dequeue()),
+			Call::ConnectFour(pallet_connectfour::Call::post_challenge(Default::default(), 0, None, (0, u32::MAX), false, 1)),
+			Call::ConnectFour(pallet_connectfour::Call::cancel_challenge()),
+			Call::ConnectFour(pallet_connectfour::Call::accept_challenge(Default::default())),
+			Call::ConnectFour(pallet_connectfour::Call::block_account(Default::default())),
+			Call::ConnectFour(pallet_connectfour::Call::unblock_account(Default::default())),
+			Call::ConnectFour(pallet_connectfour::Call::add_friend(Default::default())),
+			Call::ConnectFour(pallet_connectfour::Call::remove_friend(Default::default())),
+		];
+		for call in player_calls.iter() {
+			assert!(ProxyType::ConnectFour.filter(call));
+			assert!(ProxyType::Any.filter(call));
+		}
+
+		let other_calls = vec![
+			Call::ConnectFour(pallet_connectfour::Call::force_end_turn(Default::default(), 0)),
+			Call::ConnectFour(pallet_connectfour::Call::void_game(Default::default())),
+			Call::ConnectFour(pallet_connectfour::Call::place_bet(Default::default(), Default::default(), 1)),
+			Call::ConnectFour(pallet_connectfour::Call::do_something(0)),
+			Call::Balances(pallet_balances::Call::transfer(AccountIdLookup::unlookup(Default::default()), 1)),
+			Call::System(frame_system::Call::remark(vec![])),
+		];
+		for call in other_calls.iter() {
+			assert!(!ProxyType::ConnectFour.filter(call));
+			assert!(ProxyType::Any.filter(call));
+		}
+	}
+
+	#[test]
+	fn connect_four_proxy_superset() {
+		assert!(ProxyType::Any.is_superset(&ProxyType::ConnectFour));
+		assert!(!ProxyType::ConnectFour.is_superset(&ProxyType::Any));
+		assert!(ProxyType::ConnectFour.is_superset(&ProxyType::ConnectFour));
+	}
+}